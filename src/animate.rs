@@ -0,0 +1,187 @@
+#![allow(dead_code)]
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageResult, RgbaImage};
+
+use crate::carve::{Carve, Observer};
+use crate::maze::{Maze, Pos};
+use crate::render::{self, RenderOptions, TileError};
+use crate::solve::Walk;
+
+#[derive(Clone, Debug)]
+pub struct AnimationOptions {
+    pub frames_per_second: u32,
+    /* How many carves or solver moves go into each frame */
+    pub steps_per_frame: usize,
+    /* How long the finished maze is held before looping */
+    pub hold_frames: usize,
+    pub render: RenderOptions,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        AnimationOptions {
+            frames_per_second: 10,
+            steps_per_frame: 1,
+            hold_frames: 10,
+            render: RenderOptions {
+                cell_size: 20,
+                wall_thickness: 2,
+                ..RenderOptions::default()
+            },
+        }
+    }
+}
+
+/* Frames of a maze being built or solved, one every steps_per_frame steps */
+pub struct Animation {
+    options: AnimationOptions,
+    frames: Vec<RgbaImage>,
+    steps: usize,
+}
+
+impl Animation {
+    pub fn new(options: AnimationOptions) -> Result<Self, TileError> {
+        // Catch bad sizes now rather than on the first frame
+        render::render(&Maze::new(1, 1), &options.render, None)?;
+        Ok(Animation {
+            options: options.clone(),
+            frames: vec![],
+            steps: 0,
+        })
+    }
+
+    pub fn frames(&self) -> &[RgbaImage] {
+        &self.frames
+    }
+
+    fn add_frame(&mut self, maze: &Maze) {
+        let image =
+            render::render(maze, &self.options.render, None).expect("Options are checked in new");
+        self.frames.push(image);
+    }
+
+    /* Call after every step, only every steps_per_frame'th is drawn */
+    pub fn step(&mut self, maze: &Maze) {
+        self.steps += 1;
+        if self
+            .steps
+            .is_multiple_of(self.options.steps_per_frame.max(1))
+        {
+            self.add_frame(maze);
+        }
+    }
+
+    /* Draws the finished maze, then holds it */
+    pub fn finish(&mut self, maze: &Maze) {
+        for _ in 0..=self.options.hold_frames {
+            self.add_frame(maze);
+        }
+        self.steps = 0;
+    }
+
+    /* Steps through a solver's moves, lighting up each cell as it's visited, then shows the path found */
+    pub fn record_walk(&mut self, maze: &Maze, walk: &Walk) {
+        let mut seen = maze.clone().clear_path();
+        for pos in walk.visited.iter() {
+            seen.at_pos_mut(*pos).path = Some(true);
+            self.step(&seen);
+        }
+        let mut solved = maze.clone().clear_path();
+        walk.path
+            .iter()
+            .flatten()
+            .for_each(|pos: &Pos| solved.at_pos_mut(*pos).path = Some(true));
+        self.finish(&solved);
+    }
+
+    pub fn write_gif<W: Write>(&self, writer: W) -> ImageResult<()> {
+        let mut encoder = GifEncoder::new(writer);
+        encoder.set_repeat(Repeat::Infinite)?;
+        let delay = Delay::from_numer_denom_ms(1000, self.options.frames_per_second.max(1));
+        encoder.encode_frames(
+            self.frames
+                .iter()
+                .map(|image| Frame::from_parts(image.clone(), 0, 0, delay)),
+        )
+    }
+
+    pub fn save_gif<P: AsRef<Path>>(&self, path: P) -> ImageResult<()> {
+        self.write_gif(BufWriter::new(File::create(path)?))
+    }
+}
+
+/* A frame for every steps_per_frame links */
+impl Observer for Animation {
+    fn carve(&mut self, maze: &Maze, step: Carve) {
+        if let Carve::Link { .. } = step {
+            self.step(maze);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use image::AnimationDecoder;
+    use image::codecs::gif::GifDecoder;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::solve::Hand;
+
+    #[test]
+    fn test_generation_frames() {
+        let options = AnimationOptions {
+            steps_per_frame: 3,
+            hold_frames: 2,
+            ..AnimationOptions::default()
+        };
+        let mut animation = Animation::new(options).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        // 4x4 has 15 links, so 5 frames of 3 then the finished maze held for 2 more
+        let maze = Maze::new(4, 4).hunt_and_kill_observed(&mut rng, &mut animation);
+        animation.finish(&maze);
+        assert_eq!(animation.frames().len(), 5 + 3);
+        assert_ne!(animation.frames()[0], animation.frames()[4]);
+        assert_eq!(
+            animation.frames()[5],
+            render::render(&maze, &animation.options.render, None).unwrap()
+        );
+
+        let mut gif = vec![];
+        animation.write_gif(Cursor::new(&mut gif)).unwrap();
+        let decoded = GifDecoder::new(Cursor::new(gif)).unwrap().into_frames();
+        let decoded = decoded.collect_frames().unwrap();
+        assert_eq!(decoded.len(), 8);
+        assert_eq!(decoded[0].delay().numer_denom_ms(), (100, 1));
+    }
+
+    #[test]
+    fn test_solver_frames() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let maze = Maze::new(5, 5).wilsons(&mut rng);
+        let walk = maze.wall_follower(Pos::new(0, 0), Pos::new(4, 4), Hand::Left);
+        let mut animation = Animation::new(AnimationOptions::default()).unwrap();
+        animation.record_walk(&maze, &walk);
+        assert_eq!(animation.frames().len(), walk.visited.len() + 11);
+    }
+
+    #[test]
+    fn test_bad_options() {
+        let options = AnimationOptions {
+            render: RenderOptions {
+                wall_thickness: 0,
+                ..RenderOptions::default()
+            },
+            ..AnimationOptions::default()
+        };
+        assert!(Animation::new(options).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+#![allow(dead_code)]
+
+use crate::maze::{Direction, Maze, Pos};
+
+/* One step of a generator at work */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Carve {
+    /* The wall on the dir side of from was knocked through */
+    Link { from: Pos, dir: Direction },
+    /* The generator got to this cell */
+    Visit(Pos),
+    /* The generator gave up on its current walk and went looking elsewhere */
+    Backtrack,
+}
+
+/* Told about every step a generator takes, along with the maze as it stands */
+pub trait Observer {
+    fn carve(&mut self, maze: &Maze, step: Carve);
+}
+
+impl<F: FnMut(&Maze, Carve)> Observer for F {
+    fn carve(&mut self, maze: &Maze, step: Carve) {
+        self(maze, step)
+    }
+}
+
+/* Keeps every step so they can be replayed later */
+impl Observer for Vec<Carve> {
+    fn carve(&mut self, _maze: &Maze, step: Carve) {
+        self.push(step);
+    }
+}
+
+/* For running a generator straight through */
+pub struct Ignore;
+
+impl Observer for Ignore {
+    fn carve(&mut self, _maze: &Maze, _step: Carve) {}
+}
+
+/* Rebuilds a maze by applying only the links, on a copy of base */
+pub fn replay<'a>(base: &Maze, steps: impl IntoIterator<Item = &'a Carve>) -> Maze {
+    let mut maze = base.clone();
+    for step in steps {
+        if let Carve::Link { from, dir } = step {
+            maze.link(*from, *dir);
+        }
+    }
+    maze
+}
+
+#[cfg(test)]
+mod test {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn walls(maze: &Maze) -> Vec<(bool, bool)> {
+        maze.cells
+            .iter()
+            .map(|cell| (cell.up, cell.right))
+            .collect()
+    }
+
+    fn links(steps: &[Carve]) -> usize {
+        steps
+            .iter()
+            .filter(|step| matches!(step, Carve::Link { .. }))
+            .count()
+    }
+
+    #[test]
+    fn test_replay_matches_generators() {
+        let base = Maze::new(6, 5);
+        let mut steps = vec![];
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let maze = base.clone().hunt_and_kill_observed(&mut rng, &mut steps);
+        assert_eq!(links(&steps), 29);
+        assert!(steps.contains(&Carve::Backtrack));
+        assert_eq!(walls(&replay(&base, &steps)), walls(&maze));
+
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        assert_eq!(
+            walls(&base.clone().hunt_and_kill_seed(&mut rng)),
+            walls(&maze)
+        );
+
+        for generate in [
+            Maze::binary_tree_observed,
+            Maze::sidewinder_observed,
+            Maze::walker_observed,
+        ] {
+            let mut steps = vec![];
+            let maze = generate(base.clone(), &mut steps);
+            assert!(maze.is_perfect_maze());
+            assert_eq!(links(&steps), 29);
+            assert_eq!(walls(&replay(&base, &steps)), walls(&maze));
+            let visits = steps
+                .iter()
+                .filter(|step| matches!(step, Carve::Visit(_)))
+                .count();
+            assert!(visits >= 30);
+        }
+    }
+
+    #[test]
+    fn test_closure_observer() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let mut link_counts = vec![];
+        Maze::new(4, 4).hunt_and_kill_observed(&mut rng, &mut |maze: &Maze, step| {
+            if let Carve::Link { .. } = step {
+                link_counts.push(maze.linked_sets().set_count());
+            }
+        });
+        // Every link joins two trees, so each one leaves one fewer
+        assert_eq!(link_counts, (1..16).rev().collect::<Vec<_>>());
+    }
+}
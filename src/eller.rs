@@ -0,0 +1,96 @@
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+use crate::maze::Cell;
+
+/*
+ * Eller's algorithm as an endless iterator of rows, bottom row first.
+ * Only the set of each column in the current row is kept, so a row's `up`
+ * links into the row yielded after it. Call `last_row` to close the maze off.
+ */
+pub struct Eller {
+    rng: ChaCha8Rng,
+    sets: Vec<Option<usize>>,
+    next_set: usize,
+}
+
+impl Eller {
+    pub fn new(width: usize, rng: ChaCha8Rng) -> Self {
+        assert!(width > 0, "Rows must be at least one cell wide");
+        Eller {
+            rng,
+            sets: vec![None; width],
+            next_set: 0,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.sets.len()
+    }
+
+    /* Cells that didn't get linked from below start in a set of their own */
+    fn fill_sets(&mut self) -> Vec<usize> {
+        for set in self.sets.iter_mut().filter(|set| set.is_none()) {
+            *set = Some(self.next_set);
+            self.next_set += 1;
+        }
+        self.sets.iter().map(|set| set.unwrap()).collect()
+    }
+
+    /* Joins the set at x + 1 into the set at x */
+    fn merge(sets: &mut [usize], x: usize) {
+        let (into, from) = (sets[x], sets[x + 1]);
+        sets.iter_mut()
+            .filter(|set| **set == from)
+            .for_each(|set| *set = into);
+    }
+
+    /* Final row, links every remaining set together and nothing goes up */
+    pub fn last_row(mut self) -> Vec<Cell> {
+        let mut sets = self.fill_sets();
+        let mut row = vec![Cell::blank(); sets.len()];
+        for x in 0..sets.len() - 1 {
+            if sets[x] != sets[x + 1] {
+                row[x].right = true;
+                Self::merge(&mut sets, x);
+            }
+        }
+        row
+    }
+}
+
+impl Iterator for Eller {
+    type Item = Vec<Cell>;
+
+    fn next(&mut self) -> Option<Vec<Cell>> {
+        let mut sets = self.fill_sets();
+        let mut row = vec![Cell::blank(); sets.len()];
+
+        // Randomly join neighbours that aren't already connected
+        for x in 0..sets.len() - 1 {
+            if sets[x] != sets[x + 1] && self.rng.random::<bool>() {
+                row[x].right = true;
+                Self::merge(&mut sets, x);
+            }
+        }
+
+        // Every set needs at least one way up or it would be cut off
+        let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (x, set) in sets.iter().enumerate() {
+            members.entry(*set).or_default().push(x);
+        }
+        self.sets = vec![None; sets.len()];
+        for columns in members.values() {
+            let ups = self.rng.random_range(1..=columns.len());
+            for x in columns.choose_multiple(&mut self.rng, ups) {
+                row[*x].up = true;
+                self.sets[*x] = Some(sets[*x]);
+            }
+        }
+        Some(row)
+    }
+}
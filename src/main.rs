@@ -1,5 +1,14 @@
+mod animate;
+mod carve;
+mod disjoint_set;
+mod eller;
 mod maze;
 mod render;
+mod search;
+mod solve;
+mod stats;
+mod svg;
+mod text;
 use image::*;
 use maze::Maze;
 use rand::SeedableRng;
@@ -7,7 +16,7 @@ use rand_chacha::ChaCha8Rng;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
-    let image_path = if args.len() == 2 {
+    let image_path = if args.len() >= 2 {
         args.get(1).unwrap()
     } else {
 //        "./images/deadends.png"
@@ -20,10 +29,16 @@ fn main() {
         .unwrap()
         .rotate180()
         .fliph();
-    let mut maze = Maze::<50>::default();
-    for x in 0..maze.self_size().min(tee.width() as usize) {
-        for y in 0..maze.self_size().min(tee.height() as usize) {
-            let luma = tee.grayscale().get_pixel(x as u32, y as u32).to_luma();
+    // Size comes from the optional WIDTHxHEIGHT argument, else the mask itself
+    let (width, height) = match args.get(2) {
+        Some(size) => parse_size(size).expect("Size should look like WIDTHxHEIGHT"),
+        None => (tee.width() as usize, tee.height() as usize),
+    };
+    let mut maze = Maze::new(width, height);
+    let mask = tee.grayscale();
+    for x in 0..maze.width().min(tee.width() as usize) {
+        for y in 0..maze.height().min(tee.height() as usize) {
+            let luma = mask.get_pixel(x as u32, y as u32).to_luma();
             if luma.0[0] < 128 {
             maze.at_mut(x, y).masked = true;
             }
@@ -33,20 +48,15 @@ fn main() {
             // }
         }
     }
-    let mut max = 0;
-    let mut max_i = 0;
-    for i in 0..100 {
-    let mut rng = ChaCha8Rng::seed_from_u64(i);
-        let mut tmp = maze.hunt_and_kill_seed(&mut rng);
-        let start = tmp.calc_longest();
-        tmp = tmp.calc_dist(start.0);
-        let tmp_max = tmp.all_cells().filter_map(|x|x.dist).max().unwrap();
-        if tmp_max > max {
-            max_i = i;
-            max = tmp_max;
-            println!("Max {max} at {max_i}");
-        }
-    }
+    let best = search::search_seeds(
+        &maze,
+        0..100,
+        1,
+        |maze, rng| maze.hunt_and_kill_seed(rng),
+        search::longest_path,
+    );
+    let max_i = best[0].seed;
+    println!("Max {} at {max_i}", best[0].score);
 
     let mut rng = ChaCha8Rng::seed_from_u64(max_i);
 maze = maze.hunt_and_kill_seed(&mut rng);
@@ -74,9 +84,17 @@ maze = maze.hunt_and_kill_seed(&mut rng);
     maze.print();
     let image = render::make_image(&maze);
     image.save("output.png").unwrap();
+    std::fs::write("output.svg", svg::make_svg(&maze, &svg::SvgOptions::default())).unwrap();
 
     //let mut bytes: Vec<u8> = Vec::new();
     //image
     //   .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
     //  .unwrap();
 }
+
+fn parse_size(size: &str) -> Option<(usize, usize)> {
+    let (width, height) = size.split_once('x')?;
+    let width = width.parse().ok().filter(|width| *width > 0)?;
+    let height = height.parse().ok().filter(|height| *height > 0)?;
+    Some((width, height))
+}
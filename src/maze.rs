@@ -1,21 +1,55 @@
 #![allow(dead_code)]
+use std::collections::VecDeque;
 use std::fmt::Display;
 
 use rand::prelude::*;
 use rand_chacha::ChaCha8Rng;
 
-enum PerfectError {
-    NotAllLinked,
-    Looped,
+use crate::carve::{Carve, Ignore, Observer};
+use crate::disjoint_set::DisjointSet;
+use crate::eller::Eller;
+use crate::text::{self, TextOptions};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PerfectError {
+    /* Cells that can't be reached from (0,0) */
+    NotAllLinked(Vec<Pos>),
+    /* A cell that can be reached from (0,0) by more than one route */
+    Looped(Pos),
 }
 
-#[derive(Copy, Clone, Default, Debug)]
+impl Display for PerfectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PerfectError::NotAllLinked(cells) => {
+                f.write_fmt(format_args!("{} cells not linked:", cells.len()))?;
+                for pos in cells {
+                    f.write_fmt(format_args!(" {pos}"))?;
+                }
+                Ok(())
+            }
+            PerfectError::Looped(pos) => f.write_fmt(format_args!("Loop at {pos}")),
+        }
+    }
+}
+
+impl std::error::Error for PerfectError {}
+
+#[derive(Copy, Clone, Debug)]
 pub struct Cell {
     pub up: bool,
     pub right: bool,
     pub dist: Option<u32>,
     pub path: Option<bool>,
     pub masked: bool,
+    /* Cost of stepping into this cell, 1 is plain floor */
+    pub weight: u32,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell::blank()
+    }
 }
 
 #[derive(Copy, Clone, Default, PartialEq, Debug)]
@@ -53,6 +87,7 @@ impl Cell {
             dist: None,
             path: None,
             masked: false,
+            weight: 1,
         }
     }
 
@@ -63,11 +98,12 @@ impl Cell {
             dist: None,
             path: None,
             masked: false,
+            weight: 1,
         }
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Direction {
     North,
     East,
@@ -76,7 +112,7 @@ pub enum Direction {
 }
 
 impl Direction {
-    fn flip(&self) -> Direction {
+    pub fn flip(&self) -> Direction {
         use Direction::*;
         match self {
             North => South,
@@ -85,18 +121,68 @@ impl Direction {
             West => East,
         }
     }
+
+    /* Facing this way, the direction on your left */
+    pub fn turn_left(&self) -> Direction {
+        use Direction::*;
+        match self {
+            North => West,
+            East => North,
+            South => East,
+            West => South,
+        }
+    }
+
+    pub fn turn_right(&self) -> Direction {
+        self.turn_left().flip()
+    }
 }
 
-const ALL: [Direction; 4] = [
+pub const ALL: [Direction; 4] = [
     Direction::North,
     Direction::West,
     Direction::South,
     Direction::East,
 ];
 
-#[derive(Copy, Clone)]
-pub struct Maze<const S: usize> {
-    pub cells: [[Cell; S]; S],
+/* How growing_tree picks the next cell to grow from out of the active list, oldest first */
+#[derive(Copy, Clone, Debug)]
+pub enum Selection {
+    /* Behaves like recursive_backtracker */
+    Newest,
+    /* Behaves like simplified_prims */
+    Random,
+    Oldest,
+    /* Newest with the given percent chance, else random */
+    NewestOrRandom(u32),
+    /* Returns an index into the active list */
+    Custom(fn(&[Pos], &mut ChaCha8Rng) -> usize),
+}
+
+impl Selection {
+    fn pick(&self, active: &[Pos], rng: &mut ChaCha8Rng) -> usize {
+        match self {
+            Selection::Newest => active.len() - 1,
+            Selection::Random => rng.random_range(0..active.len()),
+            Selection::Oldest => 0,
+            Selection::NewestOrRandom(percent) => {
+                if rng.random_range(0..100) < *percent {
+                    active.len() - 1
+                } else {
+                    rng.random_range(0..active.len())
+                }
+            }
+            Selection::Custom(pick) => pick(active, rng),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Maze {
+    width: usize,
+    height: usize,
+    /* Row major, so the cell at (x, y) lives at y * width + x */
+    pub cells: Vec<Cell>,
     pub start: Pos,
     pub end: Pos,
 }
@@ -107,65 +193,85 @@ pub static CLOSED_CELL: Cell = Cell {
     dist: None,
     path: None,
     masked: false,
+    weight: 1,
 };
 
-impl<const S: usize> Default for Maze<S> {
-    fn default() -> Self {
+impl Maze {
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0, "Maze must be at least 1x1");
         Maze {
-            cells: [[Cell::default(); S]; S],
+            width,
+            height,
+            cells: vec![Cell::default(); width * height],
             start: Pos::default(),
-            end: Pos::new(S - 1, S - 1),
+            end: Pos::new(width - 1, height - 1),
         }
     }
-}
 
-impl<const S: usize> Maze<S> {
-    pub fn size() -> usize {
-        S
+    pub fn width(&self) -> usize {
+        self.width
     }
 
-    pub fn self_size(&self) -> usize {
-        S
+    pub fn height(&self) -> usize {
+        self.height
     }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    /* Index of pos in cells, also what DisjointSet uses to name a cell */
+    pub fn index_of(&self, pos: Pos) -> usize {
+        self.index(pos.x, pos.y)
+            .unwrap_or_else(|| panic!("Looking for cell at {pos} outside of maze"))
+    }
+
     pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
-        self.at_opt(x, y).expect("Looking to set cell at ({x},{y})");
-        self.cells[y][x] = cell;
+        *self.at_mut(x, y) = cell;
     }
     pub fn at_opt(&self, x: usize, y: usize) -> Option<&Cell> {
-        self.cells.get(y)?.get(x)
+        self.cells.get(self.index(x, y)?)
     }
 
     pub fn at_pos(&self, pos: Pos) -> &Cell {
-        self.at_pos_opt(pos).expect("Looking to set cell at {pos}");
-        &self.cells[pos.y][pos.x]
+        self.at(pos.x, pos.y)
     }
 
     pub fn at_pos_mut(&mut self, pos: Pos) -> &mut Cell {
-        &mut self.cells[pos.y][pos.x]
+        self.at_mut(pos.x, pos.y)
     }
 
     pub fn at_pos_opt(&self, pos: Pos) -> Option<&Cell> {
-        self.cells.get(pos.y)?.get(pos.x)
+        self.at_opt(pos.x, pos.y)
     }
     pub fn at(&self, x: usize, y: usize) -> &Cell {
-        //let index = y * S + x;
-        // println!("{x} {y} {index}**");
-        &self.cells[y][x]
+        self.at_opt(x, y)
+            .unwrap_or_else(|| panic!("Looking for cell at ({x},{y}) outside of maze"))
     }
 
     pub fn at_mut(&mut self, x: usize, y: usize) -> &mut Cell {
-        &mut self.cells[y][x]
+        let index = self
+            .index(x, y)
+            .unwrap_or_else(|| panic!("Looking for cell at ({x},{y}) outside of maze"));
+        &mut self.cells[index]
     }
 
-    pub fn all_pos() -> impl Iterator<Item = Pos> {
-        (0..S * S).map(|value| Pos::new(value % S, value / S))
+    pub fn all_pos(&self) -> impl Iterator<Item = Pos> + use<> {
+        let width = self.width;
+        (0..self.width * self.height).map(move |value| Pos::new(value % width, value / width))
     }
 
     pub fn pos_of(&self, cell: &Cell) -> Pos {
-        Self::all_pos()
-            // [TODO] Make a contant time method of getting pos from cell
-            .find(|pos| std::ptr::eq(self.at_pos(*pos), cell))
-            .unwrap()
+        let index = self
+            .cells
+            .iter()
+            .position(|other| std::ptr::eq(other, cell))
+            .expect("Cell should belong to this maze");
+        Pos::new(index % self.width, index / self.width)
     }
 
     /* Steps into valid unmasked cell else None */
@@ -192,14 +298,14 @@ impl<const S: usize> Maze<S> {
     pub fn can_go(&self, x: usize, y: usize, direction: Direction) -> bool {
         match direction {
             Direction::North => {
-                if y >= S - 1 {
+                if y >= self.height - 1 {
                     false
                 } else {
                     self.at(x, y).up
                 }
             }
             Direction::East => {
-                if x >= S - 1 {
+                if x >= self.width - 1 {
                     false
                 } else {
                     self.at(x, y).right
@@ -222,144 +328,38 @@ impl<const S: usize> Maze<S> {
         }
     }
 
-    pub fn print(&self) {
-        let has_path = self.cells.iter().flatten().any(|cell| cell.path.is_some());
-        for y in 0..S {
-            // Flip so re render from the top down.
-
-            let y = S - 1 - y;
-            // Top
-            if y == S - 1 {
-                print!("╔");
-            } else if self.at(0, y).up {
-                print!("║");
-            } else {
-                print!("╟");
-            }
-            for x in 0..S {
-                let masked_sw = self.at(x, y).masked;
-
-                let masked_se = self.at_opt(x + 1, y).unwrap_or(&CLOSED_CELL).masked;
-                let masked_ne = self.at_opt(x + 1, y + 1).unwrap_or(&CLOSED_CELL).masked;
-                let masked_nw = self.at_opt(x, y + 1).unwrap_or(&CLOSED_CELL).masked;
-
-                let left = self.at(x, y).up || (masked_nw && masked_sw);
-                let bottom = self.at(x, y).right || (masked_se && masked_sw);
-                let right =
-                    self.at_opt(x + 1, y).unwrap_or(&CLOSED_CELL).up || (masked_se && masked_ne);
-                let top =
-                    self.at_opt(x, y + 1).unwrap_or(&CLOSED_CELL).right || (masked_nw && masked_ne);
-                if y == S - 1 && x == S - 1 {
-                    print!("═══╗")
-                } else if y == S - 1 {
-                    if bottom {
-                        print!("════")
-                    } else {
-                        print!("═══╤")
-                    }
-                } else if x == S - 1 {
-                    if left {
-                        print!("   ║");
-                    } else {
-                        print!("───╢");
-                    }
-                } else {
-                    match (left, bottom, right, top) {
-                        (false, false, false, false) => print!("───┼"),
-                        (false, false, false, true) => print!("───┬"),
-                        (false, false, true, false) => print!("───┤"),
-                        (false, false, true, true) => print!("───╮"),
-                        (false, true, false, false) => print!("───┴"),
-                        (false, true, false, true) => print!("────"),
-                        (false, true, true, false) => print!("───╯"),
-                        (false, true, true, true) => print!("─── "),
-                        (true, false, false, false) => print!("   ├"),
-                        (true, false, false, true) => print!("   ╭"),
-                        (true, false, true, false) => print!("   │"),
-                        (true, false, true, true) => print!("   │"),
-                        (true, true, false, false) => print!("   ╰"),
-                        (true, true, false, true) => print!("   ─"),
-                        (true, true, true, false) => print!("   │"),
-                        (true, true, true, true) => print!("    "),
-                    };
-                }
+    /* Opens the wall between pos and its neighbour in direction */
+    pub fn link(&mut self, pos: Pos, direction: Direction) {
+        match direction {
+            Direction::North => self.at_pos_mut(pos).up = true,
+            Direction::East => self.at_pos_mut(pos).right = true,
+            Direction::South => {
+                self.at_pos_mut(pos.shift(Direction::South).unwrap()).up = true
             }
-
-            println!();
-
-            print!("║");
-            for x in 0..S {
-                let dist: Option<u32> = self.at(x, y).dist;
-                let path: Option<bool> = self.at(x, y).path;
-                let masked = self.at(x, y).masked;
-
-                let nexted_masked = self.at_opt(x + 1, y).unwrap_or(&CLOSED_CELL).masked && false;
-                let dist_char: String = if masked {
-                    "><".to_string()
-                } else if has_path && path == Some(true) {
-                    if let Some(dist) = dist {
-                        format!("{: >2}", dist)
-                    } else {
-                        "<>".to_string()
-                    }
-                } else if has_path {
-                    "  ".to_string()
-                } else if dist.is_some() {
-                    format!("{: >2}", dist.unwrap())
-                } else {
-                    "  ".to_string()
-                };
-
-                if masked && nexted_masked {
-                    print!("   ");
-                } else if x == self.end.x && y == self.end.y && self.at(x, y).right {
-                    print!("END ");
-                } else if x == self.end.x && y == self.end.y && x == S - 1 {
-                    print!("END║");
-                } else if x == self.end.x && y == self.end.y {
-                    print!("END│");
-                } else if x == self.start.x
-                    && y == self.start.y
-                    && self.can_go(x, y, Direction::East)
-                {
-                    print!("STA ");
-                } else if x == self.start.x && y == self.start.y && x == S - 1 {
-                    print!("STA║");
-                } else if x == self.start.x && y == self.start.y {
-                    print!("STA│");
-                } else if self.at(x, y).right {
-                    print!("{dist_char}  ");
-                } else if x == S - 1 {
-                    print!("{dist_char} ║");
-                } else {
-                    print!("{dist_char} │");
-                }
+            Direction::West => {
+                self.at_pos_mut(pos.shift(Direction::West).unwrap()).right = true
             }
-            println!()
         }
-        print!("╚");
+    }
 
-        for x in 0..S {
-            if x == S - 1 {
-                print!("═══╝");
-            } else if self.at(x, 0).right {
-                print!("════");
-            } else {
-                print!("═══╧");
-            }
-        }
-        println!();
+    pub fn print(&self) {
+        print!("{}", text::make_text(self, &TextOptions::default()));
+    }
+
+    pub fn binary_tree(self) -> Self {
+        self.binary_tree_observed(&mut Ignore)
     }
 
-    pub fn binary_tree(mut self) -> Self {
+    pub fn binary_tree_observed(mut self, observer: &mut dyn Observer) -> Self {
         let mut rng = rand::rng();
-        for x in 0..S {
-            for y in 0..S {
-                let (up, right) = if x == S - 1 && y == S - 1 {
+        for x in 0..self.width {
+            for y in 0..self.height {
+                observer.carve(&self, Carve::Visit(Pos::new(x, y)));
+                let (up, right) = if x == self.width - 1 && y == self.height - 1 {
                     (false, false)
-                } else if x == S - 1 {
+                } else if x == self.width - 1 {
                     (true, false)
-                } else if y == S - 1 {
+                } else if y == self.height - 1 {
                     (false, true)
                 } else if rng.random::<bool>() {
                     (true, false)
@@ -367,25 +367,42 @@ impl<const S: usize> Maze<S> {
                     (false, true)
                 };
 
-                self.set(x, y, Cell::new(up, right))
+                self.set(x, y, Cell::new(up, right));
+                self.carved(x, y, observer);
             }
         }
         self
     }
 
-    pub fn sidewinder(mut self) -> Self {
+    /* Tells observer about the links just set on (x, y) */
+    fn carved(&self, x: usize, y: usize, observer: &mut dyn Observer) {
+        let cell = self.at(x, y);
+        let from = Pos::new(x, y);
+        for (linked, dir) in [(cell.up, Direction::North), (cell.right, Direction::East)] {
+            if linked {
+                observer.carve(self, Carve::Link { from, dir });
+            }
+        }
+    }
+
+    pub fn sidewinder(self) -> Self {
+        self.sidewinder_observed(&mut Ignore)
+    }
+
+    pub fn sidewinder_observed(mut self, observer: &mut dyn Observer) -> Self {
         let mut rng = rand::rng();
-        for y in 0..S {
+        for y in 0..self.height {
             let mut run: usize = 0;
-            for x in 0..S {
+            for x in 0..self.width {
+                observer.carve(&self, Carve::Visit(Pos::new(x, y)));
                 run += 1;
-                let (up, right) = if x == S - 1 && y == S - 1 {
+                let (up, right) = if x == self.width - 1 && y == self.height - 1 {
                     // Top Right -> No more
                     (false, false)
-                } else if x == S - 1 {
+                } else if x == self.width - 1 {
                     // Right Wall -> Only Up
                     (true, false)
-                } else if y == S - 1 || rng.random::<bool>() {
+                } else if y == self.height - 1 || rng.random::<bool>() {
                     // Top Wall or Continue run
                     (false, true)
                 } else {
@@ -398,55 +415,49 @@ impl<const S: usize> Maze<S> {
                     } else {
                         // Open up top of chosen cell
                         self.at_mut(x - pick, y).up = true;
+                        let from = Pos::new(x - pick, y);
+                        observer.carve(&self, Carve::Link { from, dir: Direction::North });
                         // Close off current cell
                         (false, false)
                     }
                 };
 
-                self.set(x, y, Cell::new(up, right))
+                self.set(x, y, Cell::new(up, right));
+                self.carved(x, y, observer);
             }
         }
         self
     }
 
-    fn connect_all(self) -> Self {
-        /*
-        self.all_cells_mut().for_each(|x|x.dist = None);
-        let start = Pos::default();
-        self.calc_dist(start);
-        let mut rng = ThreadRng::default();
-        while self.all_cells().any(|x|x.dist.is_none()) {
-            let mut current = start;
-        let target =  self.all_cells().any(|x|x.dist.is_none()) {
-            let target = self.all_cells()
-            let mut directions = ALL;
-            directions.shuffle(&mut rng);
-
-            let step = directions.iter().filter_map(|x| current.shift(*x)).next().unwrap();
-
-            let to = self.at_pos_opt(step).unwrap();
-            
-
-
-
-
-
-
-
-        self.calc_dist(start);
+    /* Knocks through walls, masked or not, until every cell is reachable from (0,0) */
+    fn connect_all(mut self) -> Self {
+        let origin = Pos::default();
+        loop {
+            self = self.calc_dist(origin);
+            let bridge = self
+                .all_pos()
+                .filter(|pos| self.at_pos(*pos).dist.is_none())
+                .find_map(|pos| {
+                    ALL.iter()
+                        .filter_map(|dir| Some((*dir, pos.shift(*dir)?)))
+                        .find(|(_, next)| self.at_pos_opt(*next).is_some_and(|c| c.dist.is_some()))
+                        .map(|(dir, _)| (pos, dir))
+                });
+            match bridge {
+                None => break,
+                Some((pos, dir)) => self.link(pos, dir),
+            }
         }
-*/
-        self
-
+        self.clear_path()
     }
 
     fn hunt_and_kill_get_next_start(
         &self,
-        visited_cells: [[bool; S]; S],
+        visited_cells: &[Vec<bool>],
         _rng: &mut ChaCha8Rng,
     ) -> Vec<(Direction, Pos)> {
         let mut nexts = vec![];
-        for pos in Self::all_pos() {
+        for pos in self.all_pos() {
             if self.at_pos(pos).masked {
                 continue;
             }
@@ -477,62 +488,69 @@ impl<const S: usize> Maze<S> {
         self.hunt_and_kill_seed(&mut rng)
     }
 
-    fn is_perfect_maze(&self) -> bool {
-        let mut maze = *self;
-        maze = maze.calc_dist(Pos::default());
-        if Maze::<S>::all_pos().any(|x| maze.at_pos(x).dist.is_none()) {
-            let xs = Maze::<S>::all_pos()
-                .filter(|x| *x != Pos::default())
-                .filter(|x| maze.at_pos(*x).dist.is_none());
-            let mut any: bool = false;
-            for _x in xs {
-                any = true;
-                //                eprintln!("Disconnected at {x}");
-            }
-            if any {
-                return false;
-            }
-        }
-        maze.at_pos(Pos::default());
-        for pos in Maze::<S>::all_pos() {
-            let dist = maze.at_pos(pos).dist.unwrap();
-            let mut one_less = 0;
-            for direction in ALL {
-                if !maze.can_go_pos(pos, direction) {
+    /* Every cell reachable from (0,0) by exactly one route, so braided mazes are never perfect */
+    pub fn is_perfect_maze(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /* Checks every cell, masked or not, is reachable from (0,0) by exactly one route */
+    pub fn validate(&self) -> Result<(), PerfectError> {
+        let origin = Pos::default();
+        let mut parent: Vec<Option<Pos>> = vec![None; self.cells.len()];
+        let mut seen = vec![false; self.cells.len()];
+        let mut loop_at = None;
+        seen[self.index_of(origin)] = true;
+        let mut next = VecDeque::from([origin]);
+        while let Some(pos) = next.pop_front() {
+            for dir in ALL.iter().filter(|dir| self.can_go_pos(pos, **dir)) {
+                let neighbour = pos.shift(*dir).unwrap();
+                if parent[self.index_of(pos)] == Some(neighbour) {
                     continue;
                 }
-                let nigh_dist = match pos.shift(direction) {
-                    None => continue,
-                    Some(pos) => maze.at_pos(pos).dist.unwrap(),
-                };
-                if dist == 0 {
-                    // Start node
+                if seen[self.index_of(neighbour)] {
+                    // Keep going so disconnected cells are still reported first
+                    loop_at.get_or_insert(neighbour);
                     continue;
                 }
-                if nigh_dist == dist - 1 {
-                    one_less += 1;
-                }
-            }
-            if one_less != 1 && pos != maze.start {
-                eprintln!("Loop at {pos}");
-                return false;
+                seen[self.index_of(neighbour)] = true;
+                parent[self.index_of(neighbour)] = Some(pos);
+                next.push_back(neighbour);
             }
         }
-        true
+
+        let disconnected = self
+            .all_pos()
+            .filter(|pos| !seen[self.index_of(*pos)])
+            .collect::<Vec<_>>();
+        if !disconnected.is_empty() {
+            return Err(PerfectError::NotAllLinked(disconnected));
+        }
+        match loop_at {
+            Some(pos) => Err(PerfectError::Looped(pos)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn hunt_and_kill_seed(self, rng: &mut ChaCha8Rng) -> Self {
+        self.hunt_and_kill_observed(rng, &mut Ignore)
     }
 
-    pub fn hunt_and_kill_seed(mut self, rng: &mut ChaCha8Rng) -> Self {
+    pub fn hunt_and_kill_observed(
+        mut self,
+        rng: &mut ChaCha8Rng,
+        observer: &mut dyn Observer,
+    ) -> Self {
         // Hold list of all visited cells
-        let mut visited_cells: [[bool; S]; S] = [[false; S]; S];
-        assert!(S > 0);
-        let start_pos = Self::all_pos().find(|x| !self.at_pos(*x).masked).unwrap();
+        let mut visited_cells = vec![vec![false; self.height]; self.width];
+        let start_pos = self.all_pos().find(|x| !self.at_pos(*x).masked).unwrap();
         visited_cells[start_pos.x][start_pos.y] = true;
+        observer.carve(&self, Carve::Visit(start_pos));
 
-        Self::all_pos().for_each(|pos| visited_cells[pos.x][pos.y] |= self.at_pos(pos).masked);
+        self.all_pos().for_each(|pos| visited_cells[pos.x][pos.y] |= self.at_pos(pos).masked);
         // While there is another valid cell
         while visited_cells.iter().flatten().any(|visited| !visited) {
             //            println!("{:?}"
-            let starts = self.hunt_and_kill_get_next_start(visited_cells, rng);
+            let starts = self.hunt_and_kill_get_next_start(&visited_cells, rng);
             let starting = match starts.first() {
                 None => {
                     self.print();
@@ -541,18 +559,9 @@ impl<const S: usize> Maze<S> {
                 Some(x) => x,
             };
 
-            match starting.0 {
-                Direction::South => {
-                    self.at_pos_mut(starting.1.shift(Direction::South).unwrap())
-                        .up = true
-                }
-                Direction::North => self.at_pos_mut(starting.1).up = true,
-                Direction::East => self.at_pos_mut(starting.1).right = true,
-                Direction::West => {
-                    self.at_pos_mut(starting.1.shift(Direction::West).unwrap())
-                        .right = true
-                }
-            }
+            observer.carve(&self, Carve::Visit(starting.1));
+            self.link(starting.1, starting.0);
+            observer.carve(&self, Carve::Link { from: starting.1, dir: starting.0 });
             let mut current = starting.1;
             // Pick a first valid cells
             visited_cells[current.x][current.y] = true;
@@ -581,6 +590,7 @@ impl<const S: usize> Maze<S> {
 
                 if let Some((dir, pos)) = directions.first() {
                     visited_cells[pos.x][pos.y] = true;
+                    observer.carve(&self, Carve::Visit(*pos));
                     path_steps.push(*pos);
                     path_dirs.push(**dir);
                     current = *pos;
@@ -598,119 +608,393 @@ impl<const S: usize> Maze<S> {
                     if self.at_pos_opt(path_current).is_none() {
                         break;
                     }
-                    match dir {
-                        Direction::North => self.at_pos_mut(path_current).up = true,
-                        Direction::West => {
-                            self.at_pos_mut(path_current.shift(Direction::West).unwrap())
-                                .right = true
-                        }
-                        Direction::South => {
-                            self.at_pos_mut(path_current.shift(Direction::South).unwrap())
-                                .up = true
-                        }
-                        Direction::East => self.at_pos_mut(path_current).right = true,
-                    }
+                    self.link(path_current, dir);
+                    observer.carve(&self, Carve::Link { from: path_current, dir });
                     //                    println!("{path_current}");
                     path_current = *pos;
                 }
             } // Add path
+            observer.carve(&self, Carve::Backtrack);
         }
         self
     }
 
-    pub fn walker(mut self) -> Self {
-        self = self.clear();
-        let mut known_cells: [[bool; S]; S] = [[false; S]; S];
-        // Make once cell known
-        let mut rng = rand::rng();
-        known_cells[rng.random_range(0..S)][rng.random_range(0..S)] = true;
+    /* Depth first carve with an explicit stack so large mazes can't overflow */
+    pub fn recursive_backtracker(mut self, rng: &mut ChaCha8Rng) -> Self {
+        let mut visited_cells = vec![vec![false; self.height]; self.width];
+        self.all_pos()
+            .for_each(|pos| visited_cells[pos.x][pos.y] = self.at_pos(pos).masked);
+
+        // Masks can split the maze, so restart in every region we haven't reached
+        while let Some(start_pos) = self.all_pos().find(|pos| !visited_cells[pos.x][pos.y]) {
+            visited_cells[start_pos.x][start_pos.y] = true;
+            let mut stack = vec![start_pos];
+            while let Some(&current) = stack.last() {
+                let directions = ALL
+                    .iter()
+                    .filter_map(|dir| Some((*dir, self.step_pos(current, *dir)?)))
+                    .filter(|(_, pos)| !visited_cells[pos.x][pos.y])
+                    .collect::<Vec<_>>();
+
+                match directions.choose(rng) {
+                    Some((dir, pos)) => {
+                        self.link(current, *dir);
+                        visited_cells[pos.x][pos.y] = true;
+                        stack.push(*pos);
+                    }
+                    // Dead end, back up until there is somewhere new to go
+                    None => {
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /* One set per cell, with cells already joined by a link sharing a set */
+    pub fn linked_sets(&self) -> DisjointSet {
+        let mut sets = DisjointSet::new(self.cells.len());
+        for pos in self.all_pos() {
+            for dir in [Direction::North, Direction::East] {
+                if let Some(next) = pos.shift(dir).filter(|_| self.can_go_pos(pos, dir)) {
+                    sets.union(self.index_of(pos), self.index_of(next));
+                }
+            }
+        }
+        sets
+    }
 
-        // Set all masked cells as known
-        Self::all_pos().for_each(|pos| known_cells[pos.x][pos.y] |= self.at_pos(pos).masked);
+    pub fn kruskal(self, rng: &mut ChaCha8Rng) -> Self {
+        let mut sets = self.linked_sets();
+        self.kruskal_with_sets(&mut sets, rng)
+    }
 
-        let mut limit = 0;
-        while known_cells.as_flattened().iter().any(|x| !*x) {
-            limit += 1;
-            if limit > 20 {
-                //break;
+    /* Kruskal's algorithm, any cells already sharing a set are never linked again */
+    pub fn kruskal_with_sets(mut self, sets: &mut DisjointSet, rng: &mut ChaCha8Rng) -> Self {
+        assert_eq!(sets.len(), self.cells.len(), "Sets should have one entry per cell");
+        let mut walls = self
+            .all_pos()
+            .filter(|pos| !self.at_pos(*pos).masked)
+            .flat_map(|pos| [(pos, Direction::North), (pos, Direction::East)])
+            .filter_map(|(pos, dir)| Some((pos, dir, self.step_pos(pos, dir)?)))
+            .collect::<Vec<_>>();
+        walls.shuffle(rng);
+
+        for (pos, dir, next) in walls {
+            if sets.union(self.index_of(pos), self.index_of(next)) {
+                self.link(pos, dir);
             }
-            let sta = known_cells
-                .iter()
-                .flatten()
-                .enumerate()
-                .map(|(index, known)| ((index / S, index % S), known))
-                .filter(|(_, known)| !**known)
-                .map(|(pos, _)| pos)
-                .next()
-                .unwrap();
+        }
+        self
+    }
 
-            let mut currentx: usize = sta.0;
-            let mut currenty: usize = sta.1;
-            let current = (currentx, currenty);
-            let mut path = vec![current];
-            loop {
-                let mut directions = ALL
+    /* Simplified Prim's, grows the maze from a random cell on its frontier */
+    pub fn simplified_prims(mut self, rng: &mut ChaCha8Rng) -> Self {
+        let mut in_maze = vec![vec![false; self.height]; self.width];
+        for region in self.regions() {
+            let first = *region.choose(rng).unwrap();
+            in_maze[first.x][first.y] = true;
+            let mut active = vec![first];
+            while !active.is_empty() {
+                let index = rng.random_range(0..active.len());
+                let current = active[index];
+                let directions = ALL
                     .iter()
-                    .filter(|direction| self.step(currentx, currenty, **direction).is_some())
+                    .filter_map(|dir| Some((*dir, self.step_pos(current, *dir)?)))
+                    .filter(|(_, pos)| !in_maze[pos.x][pos.y])
                     .collect::<Vec<_>>();
-                directions.shuffle(&mut rng);
-
-                if directions.is_empty() {
-                    println!("at ({}, {}) deadend", currentx, currenty);
-                    break;
+                match directions.choose(rng) {
+                    Some((dir, pos)) => {
+                        self.link(current, *dir);
+                        in_maze[pos.x][pos.y] = true;
+                        active.push(*pos);
+                    }
+                    None => {
+                        active.swap_remove(index);
+                    }
                 }
+            }
+        }
+        self
+    }
 
-                // [TODO] This will get stuck if it has a 1 cell wide masked dead end.
-                let direction = directions.first().unwrap();
-                //dbg!(direction, i);
-                if let Some(next) = self.step(currentx, currenty, **direction) {
-                    if let Some(index) = path.iter().position(|x| *x == next) {
-                        path.truncate(index);
+    /* True Prim's, links the lightest wall on the frontier where every wall has a random weight */
+    pub fn true_prims(mut self, rng: &mut ChaCha8Rng) -> Self {
+        use std::cmp::Reverse;
+        use std::collections::BinaryHeap;
+
+        let mut in_maze = vec![vec![false; self.height]; self.width];
+        for region in self.regions() {
+            let mut frontier = BinaryHeap::new();
+            let mut joined = Some(*region.choose(rng).unwrap());
+            while let Some(current) = joined.take() {
+                in_maze[current.x][current.y] = true;
+                // A wall only gets a weight once, as its far side is in the maze by the next push
+                for (dir_index, dir) in ALL.iter().enumerate() {
+                    if let Some(next) = self.step_pos(current, *dir)
+                        && !in_maze[next.x][next.y]
+                    {
+                        let weight: u32 = rng.random();
+                        frontier.push(Reverse((weight, self.index_of(current), dir_index)));
                     }
-                    path.push(next);
-                    if known_cells[next.0][next.1] {
+                }
+
+                while let Some(Reverse((_, index, dir_index))) = frontier.pop() {
+                    let pos = Pos::new(index % self.width, index / self.width);
+                    let next = pos.shift(ALL[dir_index]).unwrap();
+                    if !in_maze[next.x][next.y] {
+                        self.link(pos, ALL[dir_index]);
+                        joined = Some(next);
                         break;
                     }
-                    (currentx, currenty) = next;
                 }
             }
-            path.iter()
-                .for_each(|(x, y)| self.at_mut(*x, *y).path = Some(true));
-            let path_directions = path
+        }
+        self
+    }
+
+    pub fn growing_tree(mut self, selection: Selection, rng: &mut ChaCha8Rng) -> Self {
+        let mut in_maze = vec![vec![false; self.height]; self.width];
+        for region in self.regions() {
+            let first = *region.choose(rng).unwrap();
+            in_maze[first.x][first.y] = true;
+            let mut active = vec![first];
+            while !active.is_empty() {
+                let index = selection.pick(&active, rng);
+                let current = active[index];
+                let directions = ALL
+                    .iter()
+                    .filter_map(|dir| Some((*dir, self.step_pos(current, *dir)?)))
+                    .filter(|(_, pos)| !in_maze[pos.x][pos.y])
+                    .collect::<Vec<_>>();
+                match directions.choose(rng) {
+                    Some((dir, pos)) => {
+                        self.link(current, *dir);
+                        in_maze[pos.x][pos.y] = true;
+                        active.push(*pos);
+                    }
+                    // Keep the order so newest and oldest stay meaningful
+                    None => {
+                        active.remove(index);
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /* Fills the maze row by row from an Eller stream, masks are ignored */
+    pub fn eller(mut self, rng: &mut ChaCha8Rng) -> Self {
+        let mut rows = Eller::new(self.width, ChaCha8Rng::from_rng(rng));
+        for y in 0..self.height - 1 {
+            let row = rows.next().unwrap();
+            for (x, cell) in row.into_iter().enumerate() {
+                self.at_mut(x, y).up = cell.up;
+                self.at_mut(x, y).right = cell.right;
+            }
+        }
+        for (x, cell) in rows.last_row().into_iter().enumerate() {
+            self.at_mut(x, self.height - 1).up = cell.up;
+            self.at_mut(x, self.height - 1).right = cell.right;
+        }
+        self
+    }
+
+    /*
+     * Starts fully open and adds walls with one gap, splitting each area in two.
+     * Areas no bigger than room_size either way are left open as rooms, so a
+     * room_size of 1 gives a perfect maze. Masks are ignored.
+     */
+    pub fn recursive_division(mut self, room_size: usize, rng: &mut ChaCha8Rng) -> Self {
+        let (width, height) = (self.width, self.height);
+        for pos in self.all_pos() {
+            let cell = self.at_pos_mut(pos);
+            cell.up = pos.y < height - 1;
+            cell.right = pos.x < width - 1;
+        }
+
+        // (x, y, width, height) of the areas still to divide
+        let mut areas = vec![(0, 0, self.width, self.height)];
+        while let Some((x, y, width, height)) = areas.pop() {
+            if width <= 1 || height <= 1 || (width <= room_size && height <= room_size) {
+                continue;
+            }
+            let horizontal = match width.cmp(&height) {
+                std::cmp::Ordering::Less => true,
+                std::cmp::Ordering::Greater => false,
+                std::cmp::Ordering::Equal => rng.random(),
+            };
+            if horizontal {
+                // Wall along the top of row y + split - 1
+                let split = rng.random_range(1..height);
+                let gap = rng.random_range(0..width);
+                for wall_x in (x..x + width).filter(|wall_x| *wall_x != x + gap) {
+                    self.at_mut(wall_x, y + split - 1).up = false;
+                }
+                areas.push((x, y, width, split));
+                areas.push((x, y + split, width, height - split));
+            } else {
+                // Wall along the right of column x + split - 1
+                let split = rng.random_range(1..width);
+                let gap = rng.random_range(0..height);
+                for wall_y in (y..y + height).filter(|wall_y| *wall_y != y + gap) {
+                    self.at_mut(x + split - 1, wall_y).right = false;
+                }
+                areas.push((x, y, split, height));
+                areas.push((x + split, y, width - split, height));
+            }
+        }
+        self
+    }
+
+    /* Unmasked cells with exactly one way out */
+    pub fn dead_ends(&self) -> Vec<Pos> {
+        self.all_pos()
+            .filter(|pos| !self.at_pos(*pos).masked)
+            .filter(|pos| ALL.iter().filter(|dir| self.can_go_pos(*pos, **dir)).count() == 1)
+            .collect()
+    }
+
+    /*
+     * Removes dead ends with the given chance by linking them to a neighbour,
+     * preferring neighbours that are dead ends too. 1.0 removes them all.
+     */
+    pub fn braid(mut self, ratio: f64, rng: &mut ChaCha8Rng) -> Self {
+        assert!((0.0..=1.0).contains(&ratio), "Braid ratio should be between 0 and 1");
+        let mut dead_ends = self.dead_ends();
+        dead_ends.shuffle(rng);
+        for pos in dead_ends {
+            // Linking an earlier dead end may have already fixed this one
+            let exits = ALL.iter().filter(|dir| self.can_go_pos(pos, **dir)).count();
+            if exits != 1 || !rng.random_bool(ratio) {
+                continue;
+            }
+            let closed = ALL
                 .iter()
-                .as_slice()
-                .windows(2)
-                .map(|x| match x {
-                    [] => panic!("Empty"),
-                    [_] => panic!("One"),
-                    [from, to, ..] => (to.0 + 10 - from.0, to.1 + 10 - from.1),
-                })
-                .map(|dir| match dir {
-                    (10, 11) => Direction::North,
-                    (11, 10) => Direction::West,
-                    (10, 9) => Direction::South,
-                    (9, 10) => Direction::East,
-                    (x, y) => panic!("Unexpected ({x} {y})"),
+                .filter(|dir| !self.can_go_pos(pos, **dir))
+                .filter_map(|dir| Some((*dir, self.step_pos(pos, *dir)?)))
+                .collect::<Vec<_>>();
+            let dead_end_neighbours = closed
+                .iter()
+                .filter(|(_, next)| {
+                    ALL.iter().filter(|dir| self.can_go_pos(*next, **dir)).count() == 1
                 })
+                .copied()
                 .collect::<Vec<_>>();
+            let choices = if dead_end_neighbours.is_empty() {
+                closed
+            } else {
+                dead_end_neighbours
+            };
+            if let Some((dir, _)) = choices.choose(rng) {
+                self.link(pos, *dir);
+            }
+        }
+        self
+    }
+
+    pub fn walker(self) -> Self {
+        self.walker_observed(&mut Ignore)
+    }
+
+    pub fn walker_observed(self, observer: &mut dyn Observer) -> Self {
+        let mut rng = ChaCha8Rng::from_os_rng();
+        self.clear().wilsons_observed(&mut rng, observer)
+    }
+
+    /* Unmasked cells grouped by which ones can reach each other */
+    fn regions(&self) -> Vec<Vec<Pos>> {
+        let mut seen = vec![vec![false; self.height]; self.width];
+        let mut regions = vec![];
+        for start_pos in self.all_pos() {
+            if seen[start_pos.x][start_pos.y] || self.at_pos(start_pos).masked {
+                continue;
+            }
+            seen[start_pos.x][start_pos.y] = true;
+            let mut region = vec![];
+            let mut next = vec![start_pos];
+            while let Some(pos) = next.pop() {
+                region.push(pos);
+                for neighbour in ALL.iter().filter_map(|dir| self.step_pos(pos, *dir)) {
+                    if !seen[neighbour.x][neighbour.y] {
+                        seen[neighbour.x][neighbour.y] = true;
+                        next.push(neighbour);
+                    }
+                }
+            }
+            regions.push(region);
+        }
+        regions
+    }
+
+    /* Random walk, linking every time it steps into a new cell. Uniform but slow to finish */
+    pub fn aldous_broder(mut self, rng: &mut ChaCha8Rng) -> Self {
+        let mut visited_cells = vec![vec![false; self.height]; self.width];
+        for region in self.regions() {
+            let mut current = *region.choose(rng).unwrap();
+            visited_cells[current.x][current.y] = true;
+            let mut unvisited = region.len() - 1;
+            while unvisited > 0 {
+                let directions = ALL
+                    .iter()
+                    .filter_map(|dir| Some((*dir, self.step_pos(current, *dir)?)))
+                    .collect::<Vec<_>>();
+                let (dir, next) = *directions.choose(rng).unwrap();
+                if !visited_cells[next.x][next.y] {
+                    self.link(current, dir);
+                    visited_cells[next.x][next.y] = true;
+                    unvisited -= 1;
+                }
+                current = next;
+            }
+        }
+        self
+    }
 
-            path.iter().zip(path_directions).for_each(|(pos, dir)| {
-                known_cells[pos.0][pos.1] = true;
-                match dir {
-                    Direction::North => self.at_mut(pos.0, pos.1).up = true,
-                    Direction::West => self.at_mut(pos.0, pos.1).right = true,
-                    Direction::South => self.at_mut(pos.0, pos.1 - 1).up = true,
-                    Direction::East => self.at_mut(pos.0 - 1, pos.1).right = true,
+    /* Loop erased random walks from each unvisited cell until they hit the maze */
+    pub fn wilsons(self, rng: &mut ChaCha8Rng) -> Self {
+        self.wilsons_observed(rng, &mut Ignore)
+    }
+
+    pub fn wilsons_observed(mut self, rng: &mut ChaCha8Rng, observer: &mut dyn Observer) -> Self {
+        let mut in_maze = vec![vec![false; self.height]; self.width];
+        // Last direction the walk left each cell by, overwriting it erases any loop
+        let mut exits: Vec<Vec<Option<Direction>>> = vec![vec![None; self.height]; self.width];
+        for region in self.regions() {
+            let first = region.choose(rng).unwrap();
+            in_maze[first.x][first.y] = true;
+            observer.carve(&self, Carve::Visit(*first));
+            for &walk_start in region.iter() {
+                let mut current = walk_start;
+                while !in_maze[current.x][current.y] {
+                    let directions = ALL
+                        .iter()
+                        .filter_map(|dir| Some((*dir, self.step_pos(current, *dir)?)))
+                        .collect::<Vec<_>>();
+                    let (dir, next) = *directions.choose(rng).unwrap();
+                    exits[current.x][current.y] = Some(dir);
+                    current = next;
+                    observer.carve(&self, Carve::Visit(current));
+                }
+
+                let mut current = walk_start;
+                while !in_maze[current.x][current.y] {
+                    let dir = exits[current.x][current.y].unwrap();
+                    in_maze[current.x][current.y] = true;
+                    self.link(current, dir);
+                    observer.carve(&self, Carve::Link { from: current, dir });
+                    current = current.shift(dir).unwrap();
                 }
-            });
+            }
         }
         self
     }
 
     fn random(mut self) -> Self {
         let mut rng = rand::rng();
-        for y in 0..S {
-            for x in 0..S {
+        for y in 0..self.height {
+            for x in 0..self.width {
                 self.set(x, y, Cell::new(rng.random(), rng.random()));
             }
         }
@@ -719,8 +1003,8 @@ impl<const S: usize> Maze<S> {
 
     fn rights(mut self) -> Self {
         let mut rng = rand::rng();
-        for y in 0..S {
-            for x in 0..S {
+        for y in 0..self.height {
+            for x in 0..self.width {
                 self.set(x, y, Cell::new(rng.random(), true))
             }
         }
@@ -729,28 +1013,33 @@ impl<const S: usize> Maze<S> {
 
     fn ups(mut self) -> Self {
         let mut rng = rand::rng();
-        for y in 0..S {
-            for x in 0..S {
+        for y in 0..self.height {
+            for x in 0..self.width {
                 self.set(x, y, Cell::new(true, rng.random()));
             }
         }
         self
     }
 
+    /* Marks the path from end back to start, needs calc_dist(start) first */
     pub fn shortist_path(mut self) -> Self {
         let mut x = self.end.x;
         let mut y = self.end.y;
-        let current = self.end;
         let mut limit = 0;
-        while current != self.start {
+        loop {
             limit += 1;
-            if limit > 1000 {
+            if limit > self.cells.len() {
                 println!("Looping in shortest path");
                 return self;
             }
             let current = self.at_mut(x, y);
             current.path = Some(true);
-            let dist = current.dist.expect(format!("Maze should have a current distance {x} {y}").as_str());
+            let dist = current
+                .dist
+                .unwrap_or_else(|| panic!("Maze should have a current distance {x} {y}"));
+            if Pos::new(x, y) == self.start || dist == 0 {
+                break;
+            }
 
             let nexts = [
                 (x.saturating_sub(1), y),
@@ -770,13 +1059,13 @@ impl<const S: usize> Maze<S> {
                 .filter(|(_, dir)| self.can_go(x, y, *dir))
                 .map(|(pos, _)| pos)
                 .filter(|(x, y)| self.at_opt(*x, *y).is_some())
-                .find(|(x, y)| self.at(*x, *y).dist.unwrap() == dist.saturating_sub(1));
-            if next_step.is_none() {
+                .find(|(x, y)| self.at(*x, *y).dist == Some(dist - 1));
+            let Some(&(next_x, next_y)) = next_step else {
                 println!("Failed to find shortest path");
                 return self;
-            }
-            x = next_step.unwrap().0;
-            y = next_step.unwrap().1;
+            };
+            x = next_x;
+            y = next_y;
         }
         self
     }
@@ -784,14 +1073,11 @@ impl<const S: usize> Maze<S> {
     pub fn calc_dist(mut self, start: Pos) -> Self {
         self.cells
             .iter_mut()
-            .flatten()
             .for_each(|cell| cell.dist = None);
-        let mut next = vec![(start.x, start.y)];
+        // Breadth first, so the first time a cell is reached is the shortest way there
+        let mut next = VecDeque::from([(start.x, start.y)]);
         self.at_pos_mut(start).dist = Some(0);
-        let mut _count = 0;
-        while let Some((x, y)) = next.pop() {
-            //dbg!(&next);
-            //dbg!(&next);
+        while let Some((x, y)) = next.pop_front() {
             let cell = self.at_opt(x, y);
             if cell.is_none() {
                 continue;
@@ -801,43 +1087,101 @@ impl<const S: usize> Maze<S> {
             }
             let dist = cell.unwrap().dist.unwrap();
             //println!("{x} {y} {dist}");
-            if x >= S {
+            if x >= self.width {
                 continue;
             }
-            if y >= S {
+            if y >= self.height {
                 continue;
             }
             if self.can_go(x, y, Direction::North) && self.at(x, y + 1).dist.is_none() {
                 self.at_mut(x, y + 1).dist = Some(dist + 1);
-                next.push((x, y + 1));
+                next.push_back((x, y + 1));
             }
 
             if self.can_go(x, y, Direction::South) && self.at(x, y - 1).dist.is_none() {
                 self.at_mut(x, y - 1).dist = Some(dist + 1);
-                next.push((x, y - 1));
+                next.push_back((x, y - 1));
             }
 
             if self.can_go(x, y, Direction::West) && self.at(x - 1, y).dist.is_none() {
                 self.at_mut(x - 1, y).dist = Some(dist + 1);
-                next.push((x - 1, y));
+                next.push_back((x - 1, y));
             }
 
             if self.can_go(x, y, Direction::East) && self.at(x + 1, y).dist.is_none() {
                 self.at_mut(x + 1, y).dist = Some(dist + 1);
-                next.push((x + 1, y));
+                next.push_back((x + 1, y));
             }
         }
 
         self
     }
 
+    /* Like calc_dist but dist is the cheapest total weight of the cells stepped into */
+    pub fn calc_weighted_dist(mut self, start: Pos) -> Self {
+        use std::cmp::Reverse;
+        use std::collections::BinaryHeap;
+
+        self.cells.iter_mut().for_each(|cell| cell.dist = None);
+        self.at_pos_mut(start).dist = Some(0);
+        let mut next = BinaryHeap::from([Reverse((0, self.index_of(start)))]);
+        while let Some(Reverse((dist, index))) = next.pop() {
+            let pos = Pos::new(index % self.width, index / self.width);
+            if self.at_pos(pos).dist.is_some_and(|best| best < dist) {
+                // Already found a cheaper way here
+                continue;
+            }
+            for dir in ALL {
+                if !self.can_go_pos(pos, dir) {
+                    continue;
+                }
+                let neighbour = self.index_of(pos.shift(dir).unwrap());
+                let cell = &mut self.cells[neighbour];
+                let new_dist = dist + cell.weight;
+                if cell.dist.is_none_or(|best| new_dist < best) {
+                    cell.dist = Some(new_dist);
+                    next.push(Reverse((new_dist, neighbour)));
+                }
+            }
+        }
+        self
+    }
+
+    /* Marks the cheapest path from end back to start, needs calc_weighted_dist(start) first.
+    Nothing is marked if the distances don't lead back to start */
+    pub fn cheapest_path(mut self) -> Self {
+        let mut current = self.end;
+        let mut path = vec![];
+        for _ in 0..self.cells.len() {
+            path.push(current);
+            let Cell { dist, weight, .. } = *self.at_pos(current);
+            let dist = dist.unwrap_or_else(|| panic!("Maze should have a current distance {current}"));
+            if current == self.start {
+                path.iter().for_each(|pos| self.at_pos_mut(*pos).path = Some(true));
+                return self;
+            }
+            let next_step = ALL
+                .iter()
+                .filter(|dir| self.can_go_pos(current, **dir))
+                .filter_map(|dir| current.shift(*dir))
+                // Zero weight cells share a dist with the cell before them, so never step back
+                .filter(|pos| !path.contains(pos))
+                .find(|pos| self.at_pos(*pos).dist == dist.checked_sub(weight));
+            match next_step {
+                Some(pos) => current = pos,
+                None => return self,
+            }
+        }
+        self
+    }
+
     pub fn calc_longest(&mut self) -> (Pos, Pos) {
-        let mut start = Self::all_pos()
+        let mut start = self.all_pos()
             .find(|pos| !self.at_pos(*pos).masked)
             .expect("Should be at lease one valid position");
         for pass in 0..3 {
             // Calculate distances from max
-            *self = self.calc_dist(start);
+            *self = self.clone().calc_dist(start);
             // Find cell farthest from max
             start = self.pos_of(self.all_cells().fold(self.at_pos(start), |max, cell| {
                 if cell.dist > max.dist { cell } else { max }
@@ -853,8 +1197,40 @@ impl<const S: usize> Maze<S> {
         (self.start, self.end)
     }
 
+    /* Cells marked as path in order, from start if it's on the path, else from whichever end is first */
+    pub fn path_cells(&self) -> Vec<Pos> {
+        let on_path = |pos: Pos| self.at_pos(pos).path == Some(true);
+        let path_neighbours = |pos: Pos| {
+            ALL.into_iter()
+                .filter(move |dir| self.can_go_pos(pos, *dir))
+                .filter_map(move |dir| pos.shift(dir))
+                .filter(move |next| on_path(*next))
+        };
+        let first = if self.at_pos_opt(self.start).is_some() && on_path(self.start) {
+            Some(self.start)
+        } else {
+            self.all_pos()
+                .filter(|pos| on_path(*pos))
+                .find(|pos| path_neighbours(*pos).count() <= 1)
+        };
+        let Some(mut current) = first else {
+            return vec![];
+        };
+        let mut path = vec![current];
+        let mut previous = None;
+        while let Some(next) = path_neighbours(current).find(|next| Some(*next) != previous) {
+            if path.contains(&next) {
+                break;
+            }
+            path.push(next);
+            previous = Some(current);
+            current = next;
+        }
+        path
+    }
+
     pub fn clear_path(mut self) -> Self {
-        self.cells.iter_mut().flatten().for_each(|cell| {
+        self.cells.iter_mut().for_each(|cell| {
             cell.dist = None;
             cell.path = None;
         });
@@ -862,16 +1238,16 @@ impl<const S: usize> Maze<S> {
     }
 
     pub fn all_cells(&self) -> impl Iterator<Item = &Cell> {
-        self.cells.iter().flatten()
+        self.cells.iter()
     }
 
     pub fn all_cells_mut(&mut self) -> impl Iterator<Item = &mut Cell> {
-        self.cells.iter_mut().flatten()
+        self.cells.iter_mut()
     }
 
     pub fn clear(mut self) -> Self {
         self.start = Pos::new(0, 0);
-        self.end = Pos::new(S - 1, S - 1);
+        self.end = Pos::new(self.width - 1, self.height - 1);
         for cell in self.all_cells_mut() {
             cell.up = false;
             cell.right = false;
@@ -888,11 +1264,11 @@ mod test {
 
     #[test]
     fn test_hunt_and_kill_get_next_start_single() {
-        let maze: Maze<4> = Maze::default();
+        let maze = Maze::new(4, 4);
         let mut rng = ChaCha8Rng::seed_from_u64(12345);
-        let mut touched: [[bool; 4]; 4] = [[false; 4]; 4];
+        let mut touched = vec![vec![false; 4]; 4];
         touched[2][2] = true;
-        let next = maze.hunt_and_kill_get_next_start(touched, &mut rng);
+        let next = maze.hunt_and_kill_get_next_start(&touched, &mut rng);
         dbg!(&next);
         assert_eq!(next.len(), 4);
         assert!(next.iter().any(|x| x.1.x == 1 && x.1.y == 2));
@@ -903,9 +1279,9 @@ mod test {
 
     #[test]
     fn test_hunt_and_kill_get_next_start_dual() {
-        let maze: Maze<4> = Maze::default();
+        let maze = Maze::new(4, 4);
         let mut rng = ChaCha8Rng::seed_from_u64(12345);
-        let mut touched: [[bool; 4]; 4] = [[false; 4]; 4];
+        let mut touched = vec![vec![false; 4]; 4];
         touched[1][2] = true;
         touched[3][2] = true;
 
@@ -922,27 +1298,27 @@ mod test {
             [None, Some(Direction::North), None, Some(Direction::North)],
         ];
         let next = maze
-            .hunt_and_kill_get_next_start(touched, &mut rng)
+            .hunt_and_kill_get_next_start(&touched, &mut rng)
             .iter()
             .map(|(_, pos)| *pos)
             .collect::<Vec<_>>();
         assert_eq!(next.len(), 6);
-        for pos in Maze::<4>::all_pos() {
+        for pos in maze.all_pos() {
             if correct[pos.x][pos.y] {
                 assert!(next.contains(&pos))
             } else {
                 assert!(!next.contains(&pos))
             }
         }
-        let next = maze.hunt_and_kill_get_next_start(touched, &mut rng);
+        let next = maze.hunt_and_kill_get_next_start(&touched, &mut rng);
         dbg!(&next);
     }
 
     #[test]
     fn test_hunt_and_kill_perfect_maze_fail() {
         for _ in 0..100 {
-            let mut maze = Maze::<10>::default();
-            for pos in Maze::<10>::all_pos() {
+            let mut maze = Maze::new(10, 10);
+            for pos in maze.all_pos() {
                 if pos.x < 5 {
                     maze.at_pos_mut(pos).masked = true;
                 }
@@ -956,15 +1332,15 @@ mod test {
     #[test]
     fn test_hunt_and_kill_perfect_maze_split() {
         for i in 0..1 {
-            let mut maze = Maze::<10>::default();
-            for pos in Maze::<10>::all_pos() {
+            let mut maze = Maze::new(10, 10);
+            for pos in maze.all_pos() {
                 if pos.x < 5 {
                     maze.at_pos_mut(pos).masked = true;
                 }
             }
             maze = maze.hunt_and_kill();
             maze.print();
-            for pos in Maze::<10>::all_pos() {
+            for pos in maze.all_pos() {
                 maze.at_pos_mut(pos).masked ^= true;
             }
             maze = maze.hunt_and_kill();
@@ -978,14 +1354,401 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_hunt_and_kill_perfect_maze_rectangular() {
+        for (width, height) in [(1, 7), (7, 1), (13, 4), (3, 20)] {
+            let mut rng = ChaCha8Rng::seed_from_u64(12345);
+            let maze = Maze::new(width, height).hunt_and_kill_seed(&mut rng);
+            assert_eq!(maze.width(), width);
+            assert_eq!(maze.height(), height);
+            assert_eq!(maze.all_pos().count(), width * height);
+            assert!(maze.is_perfect_maze(), "{width}x{height}");
+        }
+    }
+
+    #[test]
+    fn test_recursive_backtracker_seeded() {
+        let maze_a = Maze::new(15, 15).recursive_backtracker(&mut ChaCha8Rng::seed_from_u64(7));
+        let maze_b = Maze::new(15, 15).recursive_backtracker(&mut ChaCha8Rng::seed_from_u64(7));
+        let links = |maze: &Maze| maze.all_cells().map(|c| (c.up, c.right)).collect::<Vec<_>>();
+        assert_eq!(links(&maze_a), links(&maze_b));
+    }
+
+    #[test]
+    fn test_recursive_backtracker_large() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let maze = Maze::new(300, 300).recursive_backtracker(&mut rng);
+        assert!(maze.is_perfect_maze());
+    }
+
+    fn split_mask(maze: &mut Maze) {
+        // Masked column at x = 3 splits the maze and (5,5) is a one cell pocket
+        for pos in maze.all_pos() {
+            let pocket_wall = (4..=6).contains(&pos.x) && (4..=6).contains(&pos.y);
+            if pos.x == 3 || (pocket_wall && pos != Pos::new(5, 5)) {
+                maze.at_pos_mut(pos).masked = true;
+            }
+        }
+    }
+
+    fn assert_masked_untouched(maze: &Maze) {
+        for pos in maze.all_pos().filter(|pos| maze.at_pos(*pos).masked) {
+            assert!(ALL.iter().all(|dir| !maze.can_go_pos(pos, *dir)), "{pos}");
+        }
+    }
+
+    type Generator = fn(Maze, &mut ChaCha8Rng) -> Maze;
+
+    /* Every seeded generator that works around masks, a new one only needs a row here */
+    const GENERATORS: &[(&str, Generator)] = &[
+        ("recursive_backtracker", Maze::recursive_backtracker),
+        ("aldous_broder", Maze::aldous_broder),
+        ("wilsons", Maze::wilsons),
+        ("kruskal", Maze::kruskal),
+        ("simplified_prims", Maze::simplified_prims),
+        ("true_prims", Maze::true_prims),
+        ("growing_tree", |maze, rng| {
+            maze.growing_tree(Selection::NewestOrRandom(50), rng)
+        }),
+    ];
+
+    #[test]
+    fn test_generators_perfect_maze() {
+        for &(name, generate) in GENERATORS {
+            for (width, height) in [(12, 9), (9, 12)] {
+                for seed in 0..10 {
+                    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                    let maze = generate(Maze::new(width, height), &mut rng);
+                    assert!(maze.is_perfect_maze(), "{name} {width}x{height} {seed}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generators_masked() {
+        for &(name, generate) in GENERATORS {
+            let mut maze = Maze::new(10, 10);
+            split_mask(&mut maze);
+            let mut rng = ChaCha8Rng::seed_from_u64(12345);
+            maze = generate(maze, &mut rng);
+            assert_masked_untouched(&maze);
+            maze.all_cells_mut().for_each(|x| x.masked ^= true);
+            maze = generate(maze, &mut rng).connect_all();
+            maze.all_cells_mut().for_each(|x| x.masked = false);
+            assert!(maze.is_perfect_maze(), "{name}");
+        }
+    }
+
+    /* Counts how often each of the four spanning trees of a 2x2 maze is made */
+    fn spanning_tree_counts(generate: impl Fn(Maze, &mut ChaCha8Rng) -> Maze) -> Vec<u32> {
+        let mut counts = std::collections::HashMap::new();
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        for _ in 0..4000 {
+            let maze = generate(Maze::new(2, 2), &mut rng);
+            assert!(maze.is_perfect_maze());
+            let links = maze.all_cells().map(|c| (c.up, c.right)).collect::<Vec<_>>();
+            *counts.entry(links).or_insert(0) += 1;
+        }
+        counts.into_values().collect()
+    }
+
+    #[test]
+    fn test_aldous_broder_uniform() {
+        let counts = spanning_tree_counts(|maze, rng| maze.aldous_broder(rng));
+        assert_eq!(counts.len(), 4);
+        assert!(counts.iter().all(|count| (900..1100).contains(count)), "{counts:?}");
+    }
+
+    #[test]
+    fn test_wilsons_uniform() {
+        let counts = spanning_tree_counts(|maze, rng| maze.wilsons(rng));
+        assert_eq!(counts.len(), 4);
+        assert!(counts.iter().all(|count| (900..1100).contains(count)), "{counts:?}");
+    }
+
+    #[test]
+    fn test_kruskal_keeps_existing_corridor() {
+        let mut maze = Maze::new(8, 8);
+        for x in 0..7 {
+            maze.link(Pos::new(x, 4), Direction::East);
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        maze = maze.kruskal(&mut rng);
+        assert!(maze.is_perfect_maze());
+        assert!((0..7).all(|x| maze.at(x, 4).right));
+    }
+
+    #[test]
+    fn test_kruskal_with_sets() {
+        // Joining the sets without a link keeps kruskal from linking the two cells
+        let maze = Maze::new(2, 1);
+        let mut sets = maze.linked_sets();
+        sets.union(maze.index_of(Pos::new(0, 0)), maze.index_of(Pos::new(1, 0)));
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let maze = maze.kruskal_with_sets(&mut sets, &mut rng);
+        assert!(!maze.at(0, 0).right);
+    }
+
+    #[test]
+    fn test_growing_tree_perfect_maze() {
+        let selections = [
+            Selection::Newest,
+            Selection::Random,
+            Selection::Oldest,
+            Selection::NewestOrRandom(75),
+            Selection::Custom(|active, _| active.len() / 2),
+        ];
+        for selection in selections {
+            for seed in 0..10 {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                let maze = Maze::new(12, 11).growing_tree(selection, &mut rng);
+                assert!(maze.is_perfect_maze(), "{selection:?} {seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_eller_perfect_maze() {
+        for (width, height) in [(1, 1), (1, 6), (6, 1), (10, 10), (17, 5)] {
+            for seed in 0..10 {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                let maze = Maze::new(width, height).eller(&mut rng);
+                assert!(maze.is_perfect_maze(), "{width}x{height} {seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_eller_stream() {
+        let mut rows = Eller::new(8, ChaCha8Rng::seed_from_u64(12345));
+        // Far into the stream every row still leads up
+        for row in rows.by_ref().take(10_000) {
+            assert_eq!(row.len(), 8);
+            assert!(row.iter().any(|cell| cell.up));
+        }
+        assert!(rows.last_row().iter().all(|cell| !cell.up));
+    }
+
+    #[test]
+    fn test_recursive_division_perfect_maze() {
+        for seed in 0..20 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let maze = Maze::new(14, 9).recursive_division(1, &mut rng);
+            assert!(maze.is_perfect_maze(), "{seed}");
+        }
+    }
+
+    #[test]
+    fn test_recursive_division_rooms() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let maze = Maze::new(20, 20)
+            .recursive_division(4, &mut rng)
+            .calc_dist(Pos::default());
+        assert!(maze.all_cells().all(|cell| cell.dist.is_some()));
+        assert!(!maze.is_perfect_maze());
+
+        // Nothing to divide when the whole maze fits in a room
+        let maze = Maze::new(5, 5).recursive_division(5, &mut rng);
+        assert!(maze.all_pos().all(|pos| {
+            maze.can_go_pos(pos, Direction::North) == (pos.y < 4)
+                && maze.can_go_pos(pos, Direction::East) == (pos.x < 4)
+        }));
+    }
+
+    #[test]
+    fn test_braid() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let maze = Maze::new(15, 15).recursive_backtracker(&mut rng);
+        let dead_ends = maze.dead_ends().len();
+        assert!(dead_ends > 0);
+
+        let untouched = maze.clone().braid(0.0, &mut rng);
+        assert_eq!(untouched.dead_ends().len(), dead_ends);
+        assert!(untouched.is_perfect_maze());
+
+        let half = maze.clone().braid(0.5, &mut rng);
+        assert!(half.dead_ends().len() < dead_ends);
+        assert!(!half.dead_ends().is_empty());
+        assert!(!half.is_perfect_maze());
+
+        let braided = maze.braid(1.0, &mut rng);
+        assert!(braided.dead_ends().is_empty());
+        assert!(!braided.is_perfect_maze());
+    }
+
+    #[test]
+    fn test_braid_masked() {
+        let mut maze = Maze::new(10, 10);
+        split_mask(&mut maze);
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        maze = maze.wilsons(&mut rng).braid(1.0, &mut rng);
+        assert_masked_untouched(&maze);
+        assert!(maze.dead_ends().is_empty());
+    }
+
+    /* Every cell linked to every neighbour, so loops everywhere */
+    fn open_maze(width: usize, height: usize) -> Maze {
+        let mut maze = Maze::new(width, height);
+        for pos in maze.all_pos() {
+            maze.at_pos_mut(pos).up = pos.y < height - 1;
+            maze.at_pos_mut(pos).right = pos.x < width - 1;
+        }
+        maze
+    }
+
+    #[test]
+    fn test_calc_dist_open_maze() {
+        let maze = open_maze(6, 4).calc_dist(Pos::new(2, 1));
+        for pos in maze.all_pos() {
+            let expected = pos.x.abs_diff(2) + pos.y.abs_diff(1);
+            assert_eq!(maze.at_pos(pos).dist, Some(expected as u32), "{pos}");
+        }
+    }
+
+    #[test]
+    fn test_calc_dist_ring() {
+        // A ring round the outside of a 3x3, with the middle cut off
+        let mut maze = Maze::new(3, 3);
+        for x in 0..2 {
+            maze.link(Pos::new(x, 0), Direction::East);
+            maze.link(Pos::new(x, 2), Direction::East);
+        }
+        for y in 0..2 {
+            maze.link(Pos::new(0, y), Direction::North);
+            maze.link(Pos::new(2, y), Direction::North);
+        }
+        let maze = maze.calc_dist(Pos::default());
+        assert_eq!(maze.at(2, 2).dist, Some(4));
+        assert_eq!(maze.at(0, 2).dist, Some(2));
+        assert_eq!(maze.at(2, 0).dist, Some(2));
+        assert_eq!(maze.at(1, 1).dist, None);
+    }
+
+    #[test]
+    fn test_shortest_path_with_loops() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let mut maze = Maze::new(12, 12)
+            .recursive_backtracker(&mut rng)
+            .braid(1.0, &mut rng);
+        maze.start = Pos::new(0, 0);
+        maze.end = Pos::new(11, 11);
+        let maze = maze.calc_dist(Pos::default()).shortist_path();
+        let end_dist = maze.at_pos(maze.end).dist.unwrap();
+        let path = maze.all_cells().filter(|c| c.path == Some(true)).count();
+        assert_eq!(path as u32, end_dist + 1);
+
+        let maze = open_maze(5, 5).calc_dist(Pos::default()).shortist_path();
+        assert_eq!(maze.all_cells().filter(|c| c.path == Some(true)).count(), 9);
+    }
+
+    #[test]
+    fn test_calc_longest_with_loops() {
+        let mut maze = open_maze(7, 3);
+        let (start, end) = maze.calc_longest();
+        let corners = [Pos::new(0, 0), Pos::new(6, 2), Pos::new(0, 2), Pos::new(6, 0)];
+        assert!(corners.contains(&start) && corners.contains(&end));
+        assert_eq!(maze.at_pos(end).dist, Some(8));
+    }
+
+    #[test]
+    fn test_calc_weighted_dist() {
+        // Lava across the middle row of an open maze, except at the far right
+        let mut maze = open_maze(5, 3);
+        for x in 0..4 {
+            maze.at_mut(x, 1).weight = 20;
+        }
+        maze.start = Pos::new(0, 0);
+        maze.end = Pos::new(0, 2);
+
+        let shortest = maze.clone().calc_dist(maze.start).shortist_path();
+        assert_eq!(shortest.at_pos(shortest.end).dist, Some(2));
+
+        let cheapest = maze.calc_weighted_dist(Pos::new(0, 0)).cheapest_path();
+        assert_eq!(cheapest.at_pos(cheapest.end).dist, Some(10));
+        assert_eq!(cheapest.at(4, 1).path, Some(true));
+        assert!((0..4).all(|x| cheapest.at(x, 1).path.is_none()));
+        assert_eq!(cheapest.all_cells().filter(|c| c.path == Some(true)).count(), 11);
+
+        // Weights changed after the distances were worked out leave no way back
+        let mut stale = cheapest.clear_path().calc_weighted_dist(Pos::new(0, 0));
+        stale.at_mut(0, 2).weight = 5;
+        let stale = stale.cheapest_path();
+        assert!(stale.all_cells().all(|c| c.path.is_none()));
+
+        // A free cell on the way doesn't cut the path short
+        let mut free = open_maze(4, 1);
+        free.at_mut(1, 0).weight = 0;
+        free.at_mut(2, 0).weight = 0;
+        free.start = Pos::new(0, 0);
+        free.end = Pos::new(3, 0);
+        let free = free.calc_weighted_dist(Pos::new(0, 0)).cheapest_path();
+        assert!(free.all_cells().all(|c| c.path == Some(true)));
+    }
+
+    #[test]
+    fn test_calc_weighted_dist_unit_weights() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let maze = Maze::new(10, 10)
+            .recursive_backtracker(&mut rng)
+            .braid(0.5, &mut rng);
+        let plain = maze.clone().calc_dist(Pos::default());
+        let weighted = maze.calc_weighted_dist(Pos::default());
+        for pos in plain.all_pos() {
+            assert_eq!(plain.at_pos(pos).dist, weighted.at_pos(pos).dist, "{pos}");
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let maze = Maze::new(6, 6).recursive_backtracker(&mut rng);
+        assert_eq!(maze.validate(), Ok(()));
+
+        let mut split = Maze::new(3, 2);
+        split.link(Pos::new(0, 0), Direction::East);
+        split.link(Pos::new(0, 0), Direction::North);
+        split.link(Pos::new(2, 0), Direction::North);
+        let error = split.validate().unwrap_err();
+        assert_eq!(
+            error,
+            PerfectError::NotAllLinked(vec![Pos::new(2, 0), Pos::new(1, 1), Pos::new(2, 1)])
+        );
+        assert_eq!(error.to_string(), "3 cells not linked: (2,0) (1,1) (2,1)");
+
+        let looped = open_maze(2, 2);
+        assert_eq!(looped.validate(), Err(PerfectError::Looped(Pos::new(1, 1))));
+        assert_eq!(looped.validate().unwrap_err().to_string(), "Loop at (1,1)");
+    }
+
+    #[test]
+    fn test_path_cells() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let mut maze = Maze::new(10, 10).recursive_backtracker(&mut rng);
+        let (start, end) = maze.calc_longest();
+        let maze = maze.calc_dist(start).shortist_path();
+        let path = maze.path_cells();
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&end));
+        assert_eq!(path.len() as u32, maze.at_pos(end).dist.unwrap() + 1);
+        assert!(Maze::new(3, 3).path_cells().is_empty());
+    }
+
+    #[test]
+    fn test_walker_perfect_maze() {
+        for _ in 0..20 {
+            assert!(Maze::new(10, 10).walker().is_perfect_maze());
+        }
+    }
+
     #[test]
     fn test_hunt_and_kill_perfect_maze() {
         for _ in 0..100 {
-            let maze: Maze<10> = Maze::default().hunt_and_kill().calc_dist(Pos::default());
+            let maze = Maze::new(10, 10).hunt_and_kill().calc_dist(Pos::default());
             maze.print();
             assert!(maze.is_perfect_maze());
 
-            for pos in Maze::<4>::all_pos() {
+            for pos in maze.all_pos() {
                 let cell = maze.at_pos(pos);
                 assert!(cell.dist.is_some());
                 let cell_dist = match cell.dist {
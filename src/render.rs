@@ -1,58 +1,363 @@
 #![allow(dead_code)]
 
+use std::sync::LazyLock;
+
 use crate::maze;
+use crate::text::{self, TextOptions};
 use image::*;
 use maze::Direction;
 use maze::Maze;
 use maze::Pos;
 
-const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
-const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
-const GREEN: Rgba<u8> = Rgba([0, 255, 0, 255]);
-const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
-const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
-const T_GRAY: Rgba<u8> = Rgba([128, 128, 128, 128]);
-const GRAY: Rgba<u8> = Rgba([128, 128, 128, 255]);
-
-pub fn make_image<const S: usize>(maze: &Maze<S>) -> RgbaImage {
-    let tee = ImageReader::open("images/tee.png")
-        .unwrap()
-        .decode()
-        .unwrap();
-    let tip = ImageReader::open("images/tip.png")
-        .unwrap()
-        .decode()
-        .unwrap();
-    let corner = ImageReader::open("images/corner.png")
-        .unwrap()
-        .decode()
-        .unwrap();
-    let line = ImageReader::open("images/line.png")
-        .unwrap()
-        .decode()
-        .unwrap();
-    let line_boarder = ImageReader::open("images/line_boarder.png")
-        .unwrap()
-        .decode()
-        .unwrap();
-    let four_way = ImageReader::open("images/four_way.png")
-        .unwrap()
-        .decode()
-        .unwrap();
-    let line_vert = imageops::rotate90(&line);
-    let line_boarder_vert = imageops::rotate90(&line_boarder);
-    const SCALE: u32 = 50;
-    const BOARDER: u32 = 5;
-    const CELL: u32 = SCALE - BOARDER;
-    let mut image = RgbaImage::new(SCALE * S as u32 + BOARDER, SCALE * S as u32 + BOARDER);
+pub const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+pub const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+pub const GREEN: Rgba<u8> = Rgba([0, 255, 0, 255]);
+pub const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+pub const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+pub const T_GRAY: Rgba<u8> = Rgba([128, 128, 128, 128]);
+pub const GRAY: Rgba<u8> = Rgba([128, 128, 128, 255]);
+pub const SIENNA: Rgba<u8> = Rgba([160, 82, 45, 255]);
+
+/* Colours spread between stops at 0.0 to 1.0, blending linearly in between */
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    pub stops: Vec<(f32, Rgba<u8>)>,
+}
+
+impl Gradient {
+    pub fn new(from: Rgba<u8>, to: Rgba<u8>) -> Self {
+        Gradient {
+            stops: vec![(0.0, from), (1.0, to)],
+        }
+    }
+
+    pub fn at(&self, t: f32) -> Rgba<u8> {
+        let t = t.clamp(0.0, 1.0);
+        let after = self.stops.iter().position(|(stop, _)| *stop >= t);
+        let ((start, from), (end, to)) = match after {
+            None => return self.stops.last().map_or(WHITE, |(_, colour)| *colour),
+            Some(0) => return self.stops[0].1,
+            Some(index) => (self.stops[index - 1], self.stops[index]),
+        };
+        let t = (t - start) / (end - start);
+        let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+        Rgba(std::array::from_fn(|i| mix(from.0[i], to.0[i])))
+    }
+}
+
+impl Default for Gradient {
+    /* Bright near the origin, dark green far away */
+    fn default() -> Self {
+        Gradient::new(WHITE, Rgba([0, 100, 0, 255]))
+    }
+}
+
+/* Fades from white to SIENNA as weight goes from 1 up to the heaviest cell */
+fn weight_colour(weight: u32, max_weight: u32) -> Rgba<u8> {
+    let t = (weight.saturating_sub(1)) as f32 / (max_weight.max(2) - 1) as f32;
+    Gradient::new(WHITE, SIENNA).at(t)
+}
+
+const SCALE: u32 = 50;
+const BOARDER: u32 = 5;
+const CELL: u32 = SCALE - BOARDER;
+
+#[derive(Debug)]
+pub enum TileError {
+    Image(&'static str, ImageError),
+    /* Tile name, the size it should be, the size it is */
+    Size(&'static str, (u32, u32), (u32, u32)),
+    /* RenderOptions that no walls could be drawn at */
+    Options { cell_size: u32, wall_thickness: u32 },
+}
+
+impl std::fmt::Display for TileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileError::Image(name, error) => f.write_fmt(format_args!("{name} tile: {error}")),
+            TileError::Size(name, expected, found) => f.write_fmt(format_args!(
+                "{name} tile should be {}x{} but is {}x{}",
+                expected.0, expected.1, found.0, found.1
+            )),
+            TileError::Options {
+                cell_size,
+                wall_thickness,
+            } => f.write_fmt(format_args!(
+                "wall_thickness {wall_thickness} should be above 0 and below cell_size {cell_size}"
+            )),
+        }
+    }
+}
+
+impl std::error::Error for TileError {}
+
+/* Images for the walls, all drawn lying horizontally and rotated as needed.
+Fields are private so every set goes through the size checks */
+#[derive(Clone)]
+pub struct TileSet {
+    /* Square pieces where walls meet, as wide as the wall is thick */
+    tee: DynamicImage,
+    tip: DynamicImage,
+    corner: DynamicImage,
+    four_way: DynamicImage,
+    /* Wall between two cells, as long as the space inside a cell */
+    line: DynamicImage,
+    /* Outside wall, a whole cell plus a wall thickness long */
+    line_boarder: DynamicImage,
+}
+
+const TILE_NAMES: [&str; 6] = ["tee", "tip", "corner", "four_way", "line", "line_boarder"];
+
+/* Decoded on first use and kept, so make_image doesn't decode six PNGs per call */
+static EMBEDDED: LazyLock<TileSet> = LazyLock::new(|| {
+    let tiles: [&[u8]; 6] = [
+        include_bytes!("../images/tee.png"),
+        include_bytes!("../images/tip.png"),
+        include_bytes!("../images/corner.png"),
+        include_bytes!("../images/four_way.png"),
+        include_bytes!("../images/line.png"),
+        include_bytes!("../images/line_boarder.png"),
+    ];
+    TileSet::from_images(tiles.map(load_from_memory)).expect("Embedded tiles should be valid")
+});
+
+impl TileSet {
+    /* The tiles from images/, built into the binary */
+    pub fn embedded() -> Self {
+        EMBEDDED.clone()
+    }
+
+    /* Loads tee.png, tip.png, corner.png, four_way.png, line.png and line_boarder.png from dir */
+    pub fn from_dir(dir: impl AsRef<std::path::Path>) -> Result<Self, TileError> {
+        let dir = dir.as_ref();
+        Self::from_images(TILE_NAMES.map(|name| {
+            ImageReader::open(dir.join(format!("{name}.png")))
+                .map_err(ImageError::IoError)?
+                .decode()
+        }))
+    }
+
+    /* Tiles in TILE_NAMES order, sizes checked against the tee's wall thickness */
+    fn from_images(tiles: [ImageResult<DynamicImage>; 6]) -> Result<Self, TileError> {
+        let mut loaded = vec![];
+        for (tile, name) in tiles.into_iter().zip(TILE_NAMES) {
+            loaded.push(tile.map_err(|error| TileError::Image(name, error))?);
+        }
+        let boarder = loaded[0].width();
+        let scale = loaded[5].width();
+        if scale <= boarder {
+            return Err(TileError::Size(
+                "line_boarder",
+                (boarder + 1, boarder),
+                loaded[5].dimensions(),
+            ));
+        }
+        let expected = [
+            (boarder, boarder),
+            (boarder, boarder),
+            (boarder, boarder),
+            (boarder, boarder),
+            (scale - boarder, boarder),
+            (scale, boarder),
+        ];
+        for ((tile, name), expected) in loaded.iter().zip(TILE_NAMES).zip(expected) {
+            if tile.dimensions() != expected {
+                return Err(TileError::Size(name, expected, tile.dimensions()));
+            }
+        }
+        let [tee, tip, corner, four_way, line, line_boarder] = loaded.try_into().unwrap();
+        Ok(TileSet {
+            tee,
+            tip,
+            corner,
+            four_way,
+            line,
+            line_boarder,
+        })
+    }
+
+    /* Plain blocks of colour, for drawing walls at any size */
+    pub fn solid(cell_size: u32, wall_thickness: u32, colour: Rgba<u8>) -> Self {
+        let block =
+            |width, height| DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, colour));
+        TileSet {
+            tee: block(wall_thickness, wall_thickness),
+            tip: block(wall_thickness, wall_thickness),
+            corner: block(wall_thickness, wall_thickness),
+            four_way: block(wall_thickness, wall_thickness),
+            line: block(cell_size - wall_thickness, wall_thickness),
+            line_boarder: block(cell_size, wall_thickness),
+        }
+    }
+
+    pub fn tee(&self) -> &DynamicImage {
+        &self.tee
+    }
+
+    pub fn tip(&self) -> &DynamicImage {
+        &self.tip
+    }
+
+    pub fn corner(&self) -> &DynamicImage {
+        &self.corner
+    }
+
+    pub fn four_way(&self) -> &DynamicImage {
+        &self.four_way
+    }
+
+    pub fn line(&self) -> &DynamicImage {
+        &self.line
+    }
+
+    pub fn line_boarder(&self) -> &DynamicImage {
+        &self.line_boarder
+    }
+
+    /* Distance from one wall to the next, wall included */
+    pub fn cell_size(&self) -> u32 {
+        self.line_boarder.width()
+    }
+
+    pub fn wall_thickness(&self) -> u32 {
+        self.tee.width()
+    }
+
+    pub fn new(
+        tee: DynamicImage,
+        tip: DynamicImage,
+        corner: DynamicImage,
+        four_way: DynamicImage,
+        line: DynamicImage,
+        line_boarder: DynamicImage,
+    ) -> Result<Self, TileError> {
+        Self::from_images([tee, tip, corner, four_way, line, line_boarder].map(Ok))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PathStyle {
+    /* Colour in every cell on the path */
+    Fill,
+    /* A line through the middle of each cell on the path */
+    Line {
+        width: u32,
+        rounded: bool,
+        /* Put an arrow pointing the way to the end on every n'th cell */
+        arrow_every: Option<usize>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /* Distance from one wall to the next, wall included */
+    pub cell_size: u32,
+    pub wall_thickness: u32,
+    /* Extra background all round the outside wall */
+    pub margin: u32,
+    pub background: Rgba<u8>,
+    /* Only used when no tile set is given */
+    pub wall_colour: Rgba<u8>,
+    pub path_colour: Rgba<u8>,
+    pub start_colour: Rgba<u8>,
+    pub end_colour: Rgba<u8>,
+    pub masked_colour: Rgba<u8>,
+    /* Off for the puzzle, on for the answer key */
+    pub draw_path: bool,
+    pub path_style: PathStyle,
+    /* Colour cells by dist from calc_dist, nearest at 0.0 and farthest at 1.0 */
+    pub heatmap: Option<Gradient>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            cell_size: SCALE,
+            wall_thickness: BOARDER,
+            margin: 0,
+            background: WHITE,
+            wall_colour: BLACK,
+            path_colour: BLUE,
+            start_colour: GREEN,
+            end_colour: RED,
+            masked_colour: GRAY,
+            draw_path: true,
+            path_style: PathStyle::Fill,
+            heatmap: None,
+        }
+    }
+}
+
+pub fn make_image(maze: &Maze) -> RgbaImage {
+    make_image_with_tiles(maze, &EMBEDDED)
+}
+
+pub fn make_image_with_tiles(maze: &Maze, tiles: &TileSet) -> RgbaImage {
+    let options = RenderOptions {
+        cell_size: tiles.cell_size(),
+        wall_thickness: tiles.wall_thickness(),
+        ..RenderOptions::default()
+    };
+    render(maze, &options, Some(tiles)).expect("Options are taken from the tiles")
+}
+
+/* Draws walls with tiles when given, they must match the options' sizes, else solid wall_colour */
+pub fn render(
+    maze: &Maze,
+    options: &RenderOptions,
+    tiles: Option<&TileSet>,
+) -> Result<RgbaImage, TileError> {
+    let scale = options.cell_size;
+    let boarder = options.wall_thickness;
+    if boarder == 0 || scale <= boarder {
+        return Err(TileError::Options {
+            cell_size: scale,
+            wall_thickness: boarder,
+        });
+    }
+    let inner = scale - boarder;
+    let solid;
+    let tiles = match tiles {
+        Some(tiles) => {
+            if (tiles.cell_size(), tiles.wall_thickness()) != (scale, boarder) {
+                return Err(TileError::Size(
+                    "line_boarder",
+                    (scale, boarder),
+                    tiles.line_boarder.dimensions(),
+                ));
+            }
+            tiles
+        }
+        None => {
+            solid = TileSet::solid(scale, boarder, options.wall_colour);
+            &solid
+        }
+    };
+    let TileSet {
+        tee,
+        tip,
+        corner,
+        four_way,
+        line,
+        line_boarder,
+    } = tiles;
+    let line_vert = imageops::rotate90(line);
+    let line_boarder_vert = imageops::rotate90(line_boarder);
+    let mut image = RgbaImage::new(
+        scale * maze.width() as u32 + boarder,
+        scale * maze.height() as u32 + boarder,
+    );
     for (_, _, pix) in image.enumerate_pixels_mut() {
-        *pix = Rgba([255, 255, 255, 255]);
+        *pix = options.background;
     }
     // Intersections
-    for pos in Maze::<S>::all_pos().filter(|pos| pos.x != S && pos.y != S) {
+    for pos in maze
+        .all_pos()
+        .filter(|pos| pos.x != maze.width() && pos.y != maze.height())
+    {
         let cell_root = Pos::new(
-            pos.x * SCALE as usize + BOARDER as usize,
-            pos.y * SCALE as usize + BOARDER as usize,
+            pos.x * scale as usize + boarder as usize,
+            pos.y * scale as usize + boarder as usize,
         );
         let north = pos
             .shift(Direction::North)
@@ -71,40 +376,60 @@ pub fn make_image<const S: usize>(maze: &Maze<S>) -> RgbaImage {
             // None
             (true, true, true, true) => continue,
             // One
-            (false, true, true, true) => &tip,
+            (false, true, true, true) => tip,
             (true, false, true, true) => &tip.rotate270(),
             (true, true, false, true) => &tip.rotate180(),
             (true, true, true, false) => &tip.rotate90(),
             // Line
-            (true, false, true, false) => &line,
+            (true, false, true, false) => line,
             (false, true, false, true) => &line.rotate90(),
             // Corner
-            (true, true, false, false) => &corner,
+            (true, true, false, false) => corner,
             (false, true, true, false) => &corner.rotate270(),
             (false, false, true, true) => &corner.rotate180(),
             (true, false, false, true) => &corner.rotate90(),
             // Tee
-            (true, false, false, false) => &tee,
+            (true, false, false, false) => tee,
             (false, true, false, false) => &tee.rotate270(),
             (false, false, true, false) => &tee.rotate180(),
             (false, false, false, true) => &tee.rotate90(),
-            (false, false, false, false) => &four_way,
+            (false, false, false, false) => four_way,
         };
 
         image::imageops::overlay(
             &mut image,
             this_image,
-            cell_root.x as i64 + CELL as i64,
-            cell_root.y as i64 + CELL as i64,
+            cell_root.x as i64 + inner as i64,
+            cell_root.y as i64 + inner as i64,
         );
     }
 
-    for pos in Maze::<S>::all_pos() {
+    let max_weight = maze.all_cells().map(|cell| cell.weight).max().unwrap_or(1);
+    let max_dist = maze
+        .all_cells()
+        .filter_map(|cell| cell.dist)
+        .max()
+        .unwrap_or(0);
+    for pos in maze.all_pos() {
         let cell_root = Pos::new(
-            pos.x * SCALE as usize + BOARDER as usize,
-            pos.y * SCALE as usize + BOARDER as usize,
+            pos.x * scale as usize + boarder as usize,
+            pos.y * scale as usize + boarder as usize,
         );
         let cell = maze.at_pos(pos);
+        let heat = options
+            .heatmap
+            .as_ref()
+            .zip(cell.dist)
+            .map(|(gradient, dist)| gradient.at(dist as f32 / max_dist.max(1) as f32));
+        if let Some(colour) =
+            heat.or((cell.weight > 1).then(|| weight_colour(cell.weight, max_weight)))
+        {
+            for x in 0..inner {
+                for y in 0..inner {
+                    image.put_pixel(cell_root.x as u32 + x, cell_root.y as u32 + y, colour);
+                }
+            }
+        }
         if !cell.right
             && (!maze
                 .at_pos_opt(pos.shift(Direction::East).unwrap())
@@ -115,7 +440,7 @@ pub fn make_image<const S: usize>(maze: &Maze<S>) -> RgbaImage {
             image::imageops::overlay(
                 &mut image,
                 &line_vert,
-                cell_root.x as i64 + CELL as i64,
+                cell_root.x as i64 + inner as i64,
                 cell_root.y as i64,
             );
         }
@@ -128,47 +453,119 @@ pub fn make_image<const S: usize>(maze: &Maze<S>) -> RgbaImage {
         {
             image::imageops::overlay(
                 &mut image,
-                &line,
+                line,
                 cell_root.x as i64,
-                cell_root.y as i64 + CELL as i64,
+                cell_root.y as i64 + inner as i64,
             );
         }
 
-        if let Some(true) = cell.path {
-            for x in 0..CELL {
-                for y in 0..CELL {
-                    image.put_pixel(cell_root.x as u32 + x, cell_root.y as u32 + y, BLUE);
+        if options.draw_path && options.path_style == PathStyle::Fill && cell.path == Some(true) {
+            for x in 0..inner {
+                for y in 0..inner {
+                    image.put_pixel(
+                        cell_root.x as u32 + x,
+                        cell_root.y as u32 + y,
+                        options.path_colour,
+                    );
                 }
             }
         }
 
         if cell.masked {
-            for x in 0..CELL {
-                for y in 0..CELL {
-                    image.put_pixel(cell_root.x as u32 + x, cell_root.y as u32 + y, GRAY);
+            for x in 0..inner {
+                for y in 0..inner {
+                    image.put_pixel(
+                        cell_root.x as u32 + x,
+                        cell_root.y as u32 + y,
+                        options.masked_colour,
+                    );
                 }
             }
         }
         if pos == maze.start {
-            for x in 0..CELL {
-                for y in 0..CELL {
-                    image.put_pixel(cell_root.x as u32 + x, cell_root.y as u32 + y, GREEN);
+            for x in 0..inner {
+                for y in 0..inner {
+                    image.put_pixel(
+                        cell_root.x as u32 + x,
+                        cell_root.y as u32 + y,
+                        options.start_colour,
+                    );
                 }
             }
         }
 
         if pos == maze.end {
-            for x in 0..CELL {
-                for y in 0..CELL {
-                    image.put_pixel(cell_root.x as u32 + x, cell_root.y as u32 + y, RED);
+            for x in 0..inner {
+                for y in 0..inner {
+                    image.put_pixel(
+                        cell_root.x as u32 + x,
+                        cell_root.y as u32 + y,
+                        options.end_colour,
+                    );
                 }
             }
         }
-
     }
+    if let (
+        true,
+        PathStyle::Line {
+            width,
+            rounded,
+            arrow_every,
+        },
+    ) = (options.draw_path, options.path_style)
+    {
+        let centre = |pos: Pos| {
+            (
+                (pos.x as u32 * scale + boarder + inner / 2) as i64,
+                (pos.y as u32 * scale + boarder + inner / 2) as i64,
+            )
+        };
+        let path = maze.path_cells();
+        // Pixels either side of the centre, even widths lean one further to the far side
+        let width = width.max(1) as i64;
+        let (near, far) = (width / 2, width - 1 - width / 2);
+        for step in path.windows(2) {
+            let ((x1, y1), (x2, y2)) = (centre(step[0]), centre(step[1]));
+            fill_rect(
+                &mut image,
+                x1.min(x2) - near,
+                y1.min(y2) - near,
+                x1.max(x2) + far,
+                y1.max(y2) + far,
+                options.path_colour,
+            );
+        }
+        for (index, pos) in path.iter().enumerate() {
+            let (x, y) = centre(*pos);
+            if rounded {
+                fill_circle(&mut image, x, y, width, options.path_colour);
+            } else {
+                fill_rect(
+                    &mut image,
+                    x - near,
+                    y - near,
+                    x + far,
+                    y + far,
+                    options.path_colour,
+                );
+            }
+            let arrow_here =
+                arrow_every.is_some_and(|every| every > 0 && index % every == every - 1);
+            if let (true, Some(next)) = (arrow_here, path.get(index + 1)) {
+                let (next_x, next_y) = centre(*next);
+                let direction = ((next_x - x).signum(), (next_y - y).signum());
+                fill_arrow(&mut image, x, y, direction, width * 3, options.path_colour);
+            }
+        }
+    }
+
     // BORDER
-    for pos in Maze::<S>::all_pos().filter(|pos| pos.x == 0 || pos.x == S - 1) {
-        let cell_root = Pos::new(pos.x * SCALE as usize, pos.y * SCALE as usize);
+    for pos in maze
+        .all_pos()
+        .filter(|pos| pos.x == 0 || pos.x == maze.width() - 1)
+    {
+        let cell_root = Pos::new(pos.x * scale as usize, pos.y * scale as usize);
         if pos.x == 0 {
             image::imageops::overlay(
                 &mut image,
@@ -180,143 +577,295 @@ pub fn make_image<const S: usize>(maze: &Maze<S>) -> RgbaImage {
             image::imageops::overlay(
                 &mut image,
                 &line_boarder_vert,
-                cell_root.x as i64 + SCALE as i64,
+                cell_root.x as i64 + scale as i64,
                 cell_root.y as i64,
             );
         }
     }
-    for pos in Maze::<S>::all_pos().filter(|pos| pos.y == 0 || pos.y == S - 1) {
-        let cell_root = Pos::new(pos.x * SCALE as usize, pos.y * SCALE as usize);
+    for pos in maze
+        .all_pos()
+        .filter(|pos| pos.y == 0 || pos.y == maze.height() - 1)
+    {
+        let cell_root = Pos::new(pos.x * scale as usize, pos.y * scale as usize);
         if pos.y == 0 {
             image::imageops::overlay(
                 &mut image,
-                &line_boarder,
+                line_boarder,
                 cell_root.x as i64,
                 cell_root.y as i64,
             )
         } else {
             image::imageops::overlay(
                 &mut image,
-                &line_boarder,
+                line_boarder,
                 cell_root.x as i64,
-                cell_root.y as i64 + SCALE as i64,
+                cell_root.y as i64 + scale as i64,
             );
         }
     }
 
     image = imageops::flip_horizontal(&image);
-    imageops::rotate180(&image)
+    image = imageops::rotate180(&image);
+    if options.margin == 0 {
+        return Ok(image);
+    }
+    let mut framed = RgbaImage::from_pixel(
+        image.width() + options.margin * 2,
+        image.height() + options.margin * 2,
+        options.background,
+    );
+    imageops::overlay(
+        &mut framed,
+        &image,
+        options.margin as i64,
+        options.margin as i64,
+    );
+    Ok(framed)
 }
 
-pub fn print<const S: usize>(maze: &Maze<S>) {
-    let has_path = maze.cells.iter().flatten().any(|cell| cell.path.is_some());
-    for y in 0..S {
-        // Flip so re render from the top down.
-        let y = S - 1 - y;
-        // Top
-        if y == S - 1 {
-            print!("╔");
-        } else if maze.at(0, y).up {
-            print!("║");
-        } else {
-            print!("╟");
+/* Fills from (x1, y1) to (x2, y2) inclusive, clipped to the image */
+fn fill_rect(image: &mut RgbaImage, x1: i64, y1: i64, x2: i64, y2: i64, colour: Rgba<u8>) {
+    for x in x1.max(0)..=x2.min(image.width() as i64 - 1) {
+        for y in y1.max(0)..=y2.min(image.height() as i64 - 1) {
+            image.put_pixel(x as u32, y as u32, colour);
         }
-        for x in 0..S {
-            let left = maze.at(x, y).up;
-            let bottom = maze.at(x, y).right;
-            let right = maze.at_opt(x + 1, y).unwrap_or(&maze::CLOSED_CELL).up;
-            let top = maze.at_opt(x, y + 1).unwrap_or(&maze::CLOSED_CELL).right;
-            if y == S - 1 && x == S - 1 {
-                print!("═══╗")
-            } else if y == S - 1 {
-                if bottom {
-                    print!("════")
-                } else {
-                    print!("═══╤")
-                }
-            } else if x == S - 1 {
-                if left {
-                    print!("   ║asdf");
-                } else {
-                    print!("───╢");
-                }
-            } else {
-                match (left, bottom, right, top) {
-                    (false, false, false, false) => print!("───┼"),
-                    (false, false, false, true) => print!("───┬"),
-                    (false, false, true, false) => print!("───┤"),
-                    (false, false, true, true) => print!("───╮"),
-                    (false, true, false, false) => print!("───┴"),
-                    (false, true, false, true) => print!("────"),
-                    (false, true, true, false) => print!("───╯"),
-                    (false, true, true, true) => print!("─── "),
-                    (true, false, false, false) => print!("   ├"),
-                    (true, false, false, true) => print!("   ╭"),
-                    (true, false, true, false) => print!("   │"),
-                    (true, false, true, true) => print!("   │"),
-                    (true, true, false, false) => print!("   ╰"),
-                    (true, true, false, true) => print!("   ─"),
-                    (true, true, true, false) => print!("   │"),
-                    (true, true, true, true) => print!("    "),
-                };
+    }
+}
+
+/* Disc covering the same pixels across as a line of width through (cx, cy) */
+fn fill_circle(image: &mut RgbaImage, cx: i64, cy: i64, width: i64, colour: Rgba<u8>) {
+    let (near, far) = (width / 2, width - 1 - width / 2);
+    for x in cx - near..=cx + far {
+        for y in cy - near..=cy + far {
+            // Doubled so an even width can be centred between pixels
+            let (dx, dy) = (2 * (x - cx) - (far - near), 2 * (y - cy) - (far - near));
+            let inside = dx.pow(2) + dy.pow(2) <= width.pow(2);
+            if inside && x >= 0 && y >= 0 && image.in_bounds(x as u32, y as u32) {
+                image.put_pixel(x as u32, y as u32, colour);
             }
         }
+    }
+}
 
-        println!();
-        print!("║A");
-        for x in 0..S {
-            let dist: Option<u32> = maze.at(x, y).dist;
-            let path: Option<bool> = maze.at(x, y).path;
-            let dist_char: String = if has_path && path == Some(true) {
-                //
-                //
-                //
-                if let Some(dist) = dist {
-                    format!("{: >2}", dist)
-                } else {
-                    "<>".to_string()
-                }
-            } else if has_path {
-                "AA".to_string()
-            } else if dist.is_some() {
-                format!("{: >2}", dist.unwrap())
-            } else if maze.at(x,y).masked {
-                String::from("><")
-            } else {
-                "  ".to_string()
-            };
-
-            if x == maze.end.x && y == maze.end.y && maze.at(x, y).right {
-                print!("END ");
-            } else if x == maze.end.x && y == maze.end.y && x == S - 1 {
-                print!("END║");
-            } else if x == maze.end.x && y == maze.end.y {
-                print!("ENDA│");
-            } else if x == maze.start.x && y == maze.start.y && maze.can_go(x, y, Direction::East) {
-                print!("STA ");
-            } else if x == maze.start.x && y == maze.start.y && y == S - 1 {
-                print!("STA║");
-            } else if x == maze.start.x && y == maze.start.y {
-                print!("STA│");
-            } else if maze.at(x, y).right {
-                print!("{dist_char}  ");
-            } else if x == S - 1 {
-                print!("{dist_char} ║");
-            } else {
-                print!("{dist_char} │");
+/* Triangle centred on (x, y) with its tip size / 2 ahead in direction */
+fn fill_arrow(
+    image: &mut RgbaImage,
+    x: i64,
+    y: i64,
+    direction: (i64, i64),
+    size: i64,
+    colour: Rgba<u8>,
+) {
+    let (along_x, along_y) = direction;
+    let half = size / 2;
+    for step in -half..=half {
+        // Widest at the back, narrowing to a point at the tip
+        let spread = (half - step) / 2;
+        let (bx, by) = (x + along_x * step, y + along_y * step);
+        for side in -spread..=spread {
+            let (px, py) = (bx + along_y * side, by + along_x * side);
+            if px >= 0 && py >= 0 && image.in_bounds(px as u32, py as u32) {
+                image.put_pixel(px as u32, py as u32, colour);
             }
         }
-        println!()
-    }
-    print!("╚");
-    for x in 0..S {
-        if x == S - 1 {
-            print!("═══╝");
-        } else if maze.at(x, 0).right {
-            print!("════");
-        } else {
-            print!("═══╧");
+    }
+}
+
+pub fn print(maze: &Maze) {
+    print!("{}", text::make_text(maze, &TextOptions::default()));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_embedded_tiles() {
+        let tiles = TileSet::embedded();
+        assert_eq!(tiles.line().dimensions(), (CELL, BOARDER));
+        let image = make_image_with_tiles(&Maze::new(3, 2), &tiles);
+        assert_eq!(
+            image.dimensions(),
+            (SCALE * 3 + BOARDER, SCALE * 2 + BOARDER)
+        );
+    }
+
+    #[test]
+    fn test_custom_tiles() {
+        let square = |size: u32| DynamicImage::new_rgba8(size, size);
+        let strip = |length: u32| DynamicImage::new_rgba8(length, BOARDER);
+        let tiles = TileSet::new(
+            square(BOARDER),
+            square(BOARDER),
+            square(BOARDER),
+            square(BOARDER),
+            strip(CELL),
+            strip(SCALE),
+        );
+        assert!(tiles.is_ok());
+
+        let tiles = TileSet::new(
+            square(BOARDER),
+            square(8),
+            square(BOARDER),
+            square(BOARDER),
+            strip(CELL),
+            strip(SCALE),
+        );
+        let error = tiles.err().unwrap();
+        assert_eq!(error.to_string(), "tip tile should be 5x5 but is 8x8");
+    }
+
+    #[test]
+    fn test_render_options() {
+        let mut maze = Maze::new(3, 2);
+        maze.at_mut(1, 0).path = Some(true);
+        let options = RenderOptions {
+            cell_size: 10,
+            wall_thickness: 2,
+            margin: 4,
+            ..RenderOptions::default()
+        };
+        let image = render(&maze, &options, None).unwrap();
+        assert_eq!(image.dimensions(), (10 * 3 + 2 + 8, 10 * 2 + 2 + 8));
+        assert_eq!(*image.get_pixel(0, 0), options.background);
+        // Cell (1,0) is in the bottom row once flipped the right way up
+        assert_eq!(*image.get_pixel(4 + 15, 4 + 17), options.path_colour);
+        assert_eq!(*image.get_pixel(4, 4 + 5), options.wall_colour);
+
+        let puzzle = RenderOptions {
+            draw_path: false,
+            ..options.clone()
+        };
+        let image = render(&maze, &puzzle, None).unwrap();
+        assert_eq!(*image.get_pixel(4 + 15, 4 + 17), options.background);
+
+        // The embedded tiles only fit the default sizes
+        let error = render(&maze, &options, Some(&TileSet::embedded())).err().unwrap();
+        assert!(matches!(error, TileError::Size("line_boarder", _, _)));
+        let too_thick = RenderOptions {
+            wall_thickness: 10,
+            ..options
+        };
+        let error = render(&maze, &too_thick, None).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "wall_thickness 10 should be above 0 and below cell_size 10"
+        );
+    }
+
+    #[test]
+    fn test_gradient() {
+        let black = Rgba([0, 0, 0, 255]);
+        let gradient = Gradient {
+            stops: vec![(0.0, WHITE), (0.5, RED), (1.0, black)],
+        };
+        assert_eq!(gradient.at(0.0), WHITE);
+        assert_eq!(gradient.at(0.25), Rgba([255, 128, 128, 255]));
+        assert_eq!(gradient.at(0.5), RED);
+        assert_eq!(gradient.at(0.75), Rgba([128, 0, 0, 255]));
+        assert_eq!(gradient.at(2.0), black);
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let mut maze = Maze::new(3, 1);
+        maze.link(Pos::new(0, 0), Direction::East);
+        maze.link(Pos::new(1, 0), Direction::East);
+        // Keep the start and end markers off the maze
+        maze.start = Pos::new(5, 5);
+        maze.end = Pos::new(5, 5);
+        let maze = maze.calc_dist(Pos::new(0, 0));
+        let options = RenderOptions {
+            cell_size: 10,
+            wall_thickness: 2,
+            heatmap: Some(Gradient::new(WHITE, BLUE)),
+            ..RenderOptions::default()
+        };
+        let image = render(&maze, &options, None).unwrap();
+        let middle = |x: u32| *image.get_pixel(x * 10 + 6, 6);
+        assert_eq!(middle(0), WHITE);
+        assert_eq!(middle(1), Rgba([128, 128, 255, 255]));
+        assert_eq!(middle(2), BLUE);
+    }
+
+    #[test]
+    fn test_path_line_width() {
+        let mut maze = Maze::new(3, 1);
+        maze.link(Pos::new(0, 0), Direction::East);
+        maze.link(Pos::new(1, 0), Direction::East);
+        maze.start = Pos::new(0, 0);
+        maze.end = Pos::new(2, 0);
+        let maze = maze.calc_dist(Pos::new(0, 0)).shortist_path();
+        for width in 1..=6 {
+            for rounded in [false, true] {
+                let options = RenderOptions {
+                    cell_size: 20,
+                    wall_thickness: 2,
+                    start_colour: WHITE,
+                    end_colour: WHITE,
+                    path_style: PathStyle::Line {
+                        width,
+                        rounded,
+                        arrow_every: None,
+                    },
+                    ..RenderOptions::default()
+                };
+                let image = render(&maze, &options, None).unwrap();
+                // Across the line halfway between two centres, and through a centre
+                for x in [21, 31] {
+                    let thickness = (0..image.height())
+                        .filter(|y| *image.get_pixel(x, *y) == BLUE)
+                        .count();
+                    assert_eq!(thickness, width as usize, "{width} {rounded} {x}");
+                }
+            }
         }
     }
-    println!();
+
+    #[test]
+    fn test_path_line() {
+        // Path along the bottom row then up the right hand side
+        let mut maze = Maze::new(3, 2);
+        maze.link(Pos::new(0, 0), Direction::East);
+        maze.link(Pos::new(1, 0), Direction::East);
+        maze.link(Pos::new(2, 0), Direction::North);
+        maze.start = Pos::new(0, 0);
+        maze.end = Pos::new(2, 1);
+        let maze = maze.calc_dist(Pos::new(0, 0)).shortist_path();
+        let options = RenderOptions {
+            cell_size: 20,
+            wall_thickness: 2,
+            start_colour: WHITE,
+            end_colour: WHITE,
+            path_style: PathStyle::Line {
+                width: 4,
+                rounded: true,
+                arrow_every: Some(2),
+            },
+            ..RenderOptions::default()
+        };
+        let image = render(&maze, &options, None).unwrap();
+        // Flipped so y is counted from the top, the bottom row centres sit at y = 30
+        assert_eq!(*image.get_pixel(11, 30), BLUE);
+        assert_eq!(*image.get_pixel(51, 20), BLUE);
+        // The corners of the cells stay clear
+        assert_eq!(*image.get_pixel(23, 38), WHITE);
+        // Arrow on the middle cell is wider than the line
+        assert_eq!(*image.get_pixel(28, 30 + 4), BLUE);
+        assert_eq!(*image.get_pixel(15, 30 + 4), WHITE);
+
+        let fill = RenderOptions {
+            path_style: PathStyle::Fill,
+            ..options
+        };
+        let image = render(&maze, &fill, None).unwrap();
+        assert_eq!(*image.get_pixel(23, 38), BLUE);
+    }
+
+    #[test]
+    fn test_missing_tile_dir() {
+        let error = TileSet::from_dir("no/such/tiles").err().unwrap();
+        assert!(matches!(error, TileError::Image("tee", _)));
+    }
 }
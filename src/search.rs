@@ -0,0 +1,133 @@
+#![allow(dead_code)]
+
+use std::ops::Range;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::maze::Maze;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SeedScore {
+    pub seed: u64,
+    pub score: f64,
+}
+
+/* Highest score first, lowest seed breaking ties, so the order never depends on threads */
+fn best_first(a: &SeedScore, b: &SeedScore) -> std::cmp::Ordering {
+    b.score.total_cmp(&a.score).then(a.seed.cmp(&b.seed))
+}
+
+fn keep_top(scores: &mut Vec<SeedScore>, top: usize) {
+    scores.sort_by(best_first);
+    scores.truncate(top);
+}
+
+/*
+ * Generates base with every seed in seeds, on every core, and returns the
+ * top best scoring seeds, best first. Re-run generate with
+ * ChaCha8Rng::seed_from_u64(seed) to get a winner back.
+ */
+pub fn search_seeds<G, S>(
+    base: &Maze,
+    seeds: Range<u64>,
+    top: usize,
+    generate: G,
+    score: S,
+) -> Vec<SeedScore>
+where
+    G: Fn(Maze, &mut ChaCha8Rng) -> Maze + Sync,
+    S: Fn(&Maze) -> f64 + Sync,
+{
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    search_seeds_on(threads, base, seeds, top, generate, score)
+}
+
+pub fn search_seeds_on<G, S>(
+    threads: usize,
+    base: &Maze,
+    seeds: Range<u64>,
+    top: usize,
+    generate: G,
+    score: S,
+) -> Vec<SeedScore>
+where
+    G: Fn(Maze, &mut ChaCha8Rng) -> Maze + Sync,
+    S: Fn(&Maze) -> f64 + Sync,
+{
+    let threads = threads.max(1) as u64;
+    let (generate, score) = (&generate, &score);
+    let mut scores = std::thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|thread| {
+                let seeds = seeds.clone();
+                scope.spawn(move || {
+                    let mut best = vec![];
+                    // Threads take every n'th seed so slow regions get shared out
+                    for seed in seeds.skip(thread as usize).step_by(threads as usize) {
+                        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                        let maze = generate(base.clone(), &mut rng);
+                        best.push(SeedScore {
+                            seed,
+                            score: score(&maze),
+                        });
+                        if best.len() > top * 2 {
+                            keep_top(&mut best, top);
+                        }
+                    }
+                    keep_top(&mut best, top);
+                    best
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    keep_top(&mut scores, top);
+    scores
+}
+
+/* Length of the longest route through the maze, what main has always picked by */
+pub fn longest_path(maze: &Maze) -> f64 {
+    let mut maze = maze.clone();
+    let (_, end) = maze.calc_longest();
+    maze.at_pos(end).dist.unwrap_or_default() as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_search_seeds_deterministic() {
+        let base = Maze::new(12, 12);
+        let generate = |maze: Maze, rng: &mut ChaCha8Rng| maze.hunt_and_kill_seed(rng);
+        let single = search_seeds_on(1, &base, 0..40, 5, generate, longest_path);
+        assert_eq!(single.len(), 5);
+        for threads in [2, 3, 8, 64] {
+            let many = search_seeds_on(threads, &base, 0..40, 5, generate, longest_path);
+            assert_eq!(single, many, "{threads}");
+        }
+        assert!(single.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+        // The winner really is that good when regenerated
+        let mut rng = ChaCha8Rng::seed_from_u64(single[0].seed);
+        let winner = base.clone().hunt_and_kill_seed(&mut rng);
+        assert_eq!(longest_path(&winner), single[0].score);
+    }
+
+    #[test]
+    fn test_search_seeds_custom_score() {
+        let base = Maze::new(10, 10);
+        let generate = |maze: Maze, rng: &mut ChaCha8Rng| maze.recursive_backtracker(rng);
+        let dead_ends = |maze: &Maze| maze.stats().dead_ends as f64;
+        let best = search_seeds(&base, 100..120, 50, generate, dead_ends);
+        // Asking for more than there are gives every seed
+        assert_eq!(best.len(), 20);
+        let mut seeds = best.iter().map(|score| score.seed).collect::<Vec<_>>();
+        seeds.sort();
+        assert_eq!(seeds, (100..120).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,501 @@
+#![allow(dead_code)]
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::maze::{ALL, Direction, Maze, Pos};
+
+/* Guess at the remaining cost from a cell to the end, should never overestimate.
+It needn't be consistent, a cell is looked at again whenever a cheaper way to it turns up */
+pub enum Heuristic {
+    /* Assumes every cell costs at least 1, so can overestimate once any weight is 0 */
+    Manhattan,
+    /* Always 0, which turns A* into Dijkstra */
+    Zero,
+    Custom(Box<dyn Fn(Pos, Pos) -> u32>),
+}
+
+impl Heuristic {
+    fn estimate(&self, from: Pos, to: Pos) -> u32 {
+        match self {
+            Heuristic::Manhattan => (from.x.abs_diff(to.x) + from.y.abs_diff(to.y)) as u32,
+            Heuristic::Zero => 0,
+            Heuristic::Custom(estimate) => estimate(from, to),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+/* How a person solving by hand got on */
+#[derive(Clone, Debug)]
+pub struct Walk {
+    /* Every cell in the order it was stepped on, or filled in for dead_end_filling */
+    pub visited: Vec<Pos>,
+    /* Start to end inclusive, None if the end was never found */
+    pub path: Option<Vec<Pos>>,
+}
+
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct SearchStats {
+    /* Cells taken off the frontier and looked around */
+    pub expanded: usize,
+    /* Largest the frontier got */
+    pub peak_frontier: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Search {
+    /* Start to end inclusive */
+    pub path: Vec<Pos>,
+    /* Sum of the weights of every cell stepped into */
+    pub cost: u32,
+    pub stats: SearchStats,
+}
+
+impl Maze {
+    fn neighbours(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        ALL.into_iter()
+            .filter(move |dir| self.can_go_pos(pos, *dir))
+            .filter_map(move |dir| pos.shift(dir))
+    }
+
+    fn follow_back(&self, came_from: &[Option<usize>], end: Pos) -> Vec<Pos> {
+        let mut path = vec![end];
+        let mut index = self.index_of(end);
+        while let Some(previous) = came_from[index] {
+            path.push(Pos::new(previous % self.width(), previous / self.width()));
+            index = previous;
+        }
+        path.reverse();
+        path
+    }
+
+    /* Keeps one hand on the wall. Fails when start or end sit on a loop the wall doesn't touch */
+    pub fn wall_follower(&self, start: Pos, end: Pos, hand: Hand) -> Walk {
+        let mut visited = vec![start];
+        let mut path = vec![start];
+        let mut current = start;
+        let mut facing = Direction::North;
+        // Each cell can be entered facing four ways, after that we're going round in circles
+        for _ in 0..self.cells.len() * 4 {
+            if current == end {
+                return Walk {
+                    visited,
+                    path: Some(path),
+                };
+            }
+            let (towards, away) = match hand {
+                Hand::Left => (facing.turn_left(), facing.turn_right()),
+                Hand::Right => (facing.turn_right(), facing.turn_left()),
+            };
+            let Some(dir) = [towards, facing, away, facing.flip()]
+                .into_iter()
+                .find(|dir| self.can_go_pos(current, *dir))
+            else {
+                break;
+            };
+            facing = dir;
+            current = current.shift(dir).unwrap();
+            visited.push(current);
+            // Walking back over our steps erases them from the route
+            match path.iter().position(|pos| *pos == current) {
+                Some(index) => path.truncate(index + 1),
+                None => path.push(current),
+            }
+        }
+        Walk {
+            visited,
+            path: None,
+        }
+    }
+
+    /*
+     * Trémaux's algorithm, marks each passage every time it is walked. Go back
+     * on reaching an old junction by a fresh passage, never use a passage a
+     * third time. The passages marked once are the route.
+     */
+    pub fn tremaux(&self, start: Pos, end: Pos) -> Walk {
+        let mut marks = vec![[0u8; 4]; self.cells.len()];
+        let dir_index = |dir: Direction| ALL.iter().position(|x| *x == dir).unwrap();
+        let mark = |marks: &mut Vec<[u8; 4]>, pos: Pos, dir: Direction| {
+            marks[self.index_of(pos)][dir_index(dir)] += 1;
+            let other = pos.shift(dir).unwrap();
+            marks[self.index_of(other)][dir_index(dir.flip())] += 1;
+        };
+
+        let mut visited = vec![start];
+        let mut current = start;
+        let mut came_by: Option<Direction> = None;
+        while current != end {
+            let exits = ALL
+                .into_iter()
+                .filter(|dir| self.can_go_pos(current, *dir))
+                .collect::<Vec<_>>();
+            let cell_marks = marks[self.index_of(current)];
+            let count = |dir: Direction| cell_marks[dir_index(dir)];
+            let seen_before = exits
+                .iter()
+                .filter(|dir| Some(**dir) != came_by)
+                .any(|dir| count(*dir) > 0);
+            let next = match came_by {
+                Some(back) if seen_before && count(back) == 1 => Some(back),
+                _ => exits
+                    .iter()
+                    .copied()
+                    .filter(|dir| Some(*dir) != came_by && count(*dir) < 2)
+                    .min_by_key(|dir| count(*dir))
+                    .or(came_by.filter(|back| count(*back) < 2)),
+            };
+            let Some(dir) = next else {
+                return Walk {
+                    visited,
+                    path: None,
+                };
+            };
+            mark(&mut marks, current, dir);
+            current = current.shift(dir).unwrap();
+            came_by = Some(dir.flip());
+            visited.push(current);
+        }
+
+        // The once marked passages lead from start to end
+        let mut path = vec![start];
+        let mut previous: Option<Direction> = None;
+        let mut current = start;
+        while current != end {
+            let dir = ALL
+                .into_iter()
+                .filter(|dir| Some(*dir) != previous && self.can_go_pos(current, *dir))
+                .find(|dir| marks[self.index_of(current)][dir_index(*dir)] == 1)
+                .unwrap();
+            current = current.shift(dir).unwrap();
+            previous = Some(dir.flip());
+            path.push(current);
+        }
+        Walk {
+            visited,
+            path: Some(path),
+        }
+    }
+
+    /* Fills in dead ends until only the routes between start and end are left */
+    pub fn dead_end_filling(&self, start: Pos, end: Pos) -> Walk {
+        let mut filled = vec![false; self.cells.len()];
+        let mut exits = self
+            .all_pos()
+            .map(|pos| self.neighbours(pos).count())
+            .collect::<Vec<_>>();
+        let mut dead_ends = self
+            .all_pos()
+            .filter(|pos| exits[self.index_of(*pos)] == 1)
+            .collect::<VecDeque<_>>();
+        let mut visited = vec![];
+        while let Some(pos) = dead_ends.pop_front() {
+            let index = self.index_of(pos);
+            if pos == start || pos == end || filled[index] {
+                continue;
+            }
+            filled[index] = true;
+            visited.push(pos);
+            for next in self.neighbours(pos) {
+                let next_index = self.index_of(next);
+                if !filled[next_index] {
+                    exits[next_index] -= 1;
+                    if exits[next_index] == 1 {
+                        dead_ends.push_back(next);
+                    }
+                }
+            }
+        }
+
+        // Anything left is on a route, with loops there can be more than one
+        let mut came_from = vec![None; self.cells.len()];
+        let mut seen = filled;
+        seen[self.index_of(start)] = true;
+        let mut frontier = VecDeque::from([start]);
+        while let Some(pos) = frontier.pop_front() {
+            if pos == end {
+                return Walk {
+                    visited,
+                    path: Some(self.follow_back(&came_from, end)),
+                };
+            }
+            for next in self.neighbours(pos) {
+                let index = self.index_of(next);
+                if !seen[index] {
+                    seen[index] = true;
+                    came_from[index] = Some(self.index_of(pos));
+                    frontier.push_back(next);
+                }
+            }
+        }
+        Walk {
+            visited,
+            path: None,
+        }
+    }
+
+    /* Fewest steps, ignoring weights */
+    pub fn breadth_first(&self, start: Pos, end: Pos) -> Option<Search> {
+        let mut stats = SearchStats::default();
+        let mut came_from = vec![None; self.cells.len()];
+        let mut seen = vec![false; self.cells.len()];
+        seen[self.index_of(start)] = true;
+        let mut frontier = VecDeque::from([start]);
+        while let Some(pos) = frontier.pop_front() {
+            stats.peak_frontier = stats.peak_frontier.max(frontier.len() + 1);
+            stats.expanded += 1;
+            if pos == end {
+                let path = self.follow_back(&came_from, end);
+                let cost = path.iter().skip(1).map(|pos| self.at_pos(*pos).weight).sum();
+                return Some(Search { path, cost, stats });
+            }
+            for next in self.neighbours(pos) {
+                let index = self.index_of(next);
+                if !seen[index] {
+                    seen[index] = true;
+                    came_from[index] = Some(self.index_of(pos));
+                    frontier.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /* Cheapest route by cell weight */
+    pub fn dijkstra(&self, start: Pos, end: Pos) -> Option<Search> {
+        self.a_star(start, end, &Heuristic::Zero)
+    }
+
+    /* Cheapest route by cell weight, guided towards end by the heuristic */
+    pub fn a_star(&self, start: Pos, end: Pos, heuristic: &Heuristic) -> Option<Search> {
+        let mut stats = SearchStats::default();
+        let mut came_from = vec![None; self.cells.len()];
+        let mut best = vec![None; self.cells.len()];
+        best[self.index_of(start)] = Some(0);
+        // Ties go to the cell that looks closest to the end
+        let mut frontier = BinaryHeap::from([Reverse((
+            heuristic.estimate(start, end),
+            heuristic.estimate(start, end),
+            0,
+            self.index_of(start),
+        ))]);
+        while let Some(Reverse((_, _, cost, index))) = frontier.pop() {
+            stats.peak_frontier = stats.peak_frontier.max(frontier.len() + 1);
+            if best[index].is_some_and(|known| known < cost) {
+                // Already found a cheaper way here
+                continue;
+            }
+            stats.expanded += 1;
+            let pos = Pos::new(index % self.width(), index / self.width());
+            if pos == end {
+                let path = self.follow_back(&came_from, end);
+                return Some(Search { path, cost, stats });
+            }
+            for next in self.neighbours(pos) {
+                let next_index = self.index_of(next);
+                let next_cost = cost + self.at_pos(next).weight;
+                if best[next_index].is_none_or(|known| next_cost < known) {
+                    best[next_index] = Some(next_cost);
+                    came_from[next_index] = Some(index);
+                    let estimate = heuristic.estimate(next, end);
+                    frontier.push(Reverse((
+                        next_cost + estimate,
+                        estimate,
+                        next_cost,
+                        next_index,
+                    )));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    fn braided_maze() -> Maze {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        Maze::new(20, 20)
+            .recursive_backtracker(&mut rng)
+            .braid(0.7, &mut rng)
+    }
+
+    fn assert_walkable(maze: &Maze, search: &Search, start: Pos, end: Pos) {
+        assert_eq!(search.path.first(), Some(&start));
+        assert_eq!(search.path.last(), Some(&end));
+        for step in search.path.windows(2) {
+            assert!(maze.neighbours(step[0]).any(|pos| pos == step[1]));
+        }
+    }
+
+    #[test]
+    fn test_searches_agree() {
+        let maze = braided_maze();
+        let (start, end) = (Pos::new(0, 0), Pos::new(19, 19));
+        let bfs = maze.breadth_first(start, end).unwrap();
+        let dijkstra = maze.dijkstra(start, end).unwrap();
+        let a_star = maze.a_star(start, end, &Heuristic::Manhattan).unwrap();
+        for search in [&bfs, &dijkstra, &a_star] {
+            assert_walkable(&maze, search, start, end);
+            assert_eq!(search.cost, bfs.cost);
+        }
+        let shortest = maze.calc_dist(start);
+        assert_eq!(Some(bfs.cost), shortest.at_pos(end).dist);
+        assert!(a_star.stats.expanded <= dijkstra.stats.expanded);
+        assert!(a_star.stats.peak_frontier > 0);
+    }
+
+    #[test]
+    fn test_a_star_weighted() {
+        let mut maze = braided_maze();
+        let (start, end) = (Pos::new(0, 0), Pos::new(19, 19));
+        let plain = maze.breadth_first(start, end).unwrap();
+        // Make the plain shortest route expensive
+        for pos in &plain.path[1..plain.path.len() - 1] {
+            maze.at_pos_mut(*pos).weight = 50;
+        }
+        let dijkstra = maze.dijkstra(start, end).unwrap();
+        let a_star = maze.a_star(start, end, &Heuristic::Manhattan).unwrap();
+        assert_walkable(&maze, &a_star, start, end);
+        assert_eq!(a_star.cost, dijkstra.cost);
+        let weighted = maze.calc_weighted_dist(start);
+        assert_eq!(Some(a_star.cost), weighted.at_pos(end).dist);
+    }
+
+    #[test]
+    fn test_a_star_custom_heuristic() {
+        let maze = braided_maze();
+        let (start, end) = (Pos::new(3, 17), Pos::new(15, 2));
+        let chebyshev = Heuristic::Custom(Box::new(|from: Pos, to: Pos| {
+            from.x.abs_diff(to.x).max(from.y.abs_diff(to.y)) as u32
+        }));
+        let custom = maze.a_star(start, end, &chebyshev).unwrap();
+        let bfs = maze.breadth_first(start, end).unwrap();
+        assert_walkable(&maze, &custom, start, end);
+        assert_eq!(custom.cost, bfs.cost);
+    }
+
+    #[test]
+    fn test_a_star_inconsistent_heuristic() {
+        let (start, end) = (Pos::new(0, 0), Pos::new(5, 5));
+        for seed in 0..50 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut maze = Maze::new(6, 6)
+                .recursive_backtracker(&mut rng)
+                .braid(1.0, &mut rng);
+            maze.all_cells_mut()
+                .for_each(|cell| cell.weight = rng.random_range(1..20));
+            // The true remaining cost on every third column and 0 elsewhere never
+            // overestimates, but drops sharply from one cell to the next
+            let from_end = maze.clone().calc_weighted_dist(end);
+            let end_weight = maze.at_pos(end).weight;
+            let exact = move |from: Pos, _| {
+                let cell = from_end.at_pos(from);
+                match from.x % 3 {
+                    0 => cell.dist.unwrap() + end_weight - cell.weight,
+                    _ => 0,
+                }
+            };
+            let a_star = maze
+                .a_star(start, end, &Heuristic::Custom(Box::new(exact)))
+                .unwrap();
+            assert_walkable(&maze, &a_star, start, end);
+            let dijkstra = maze.dijkstra(start, end).unwrap();
+            assert_eq!(a_star.cost, dijkstra.cost, "{seed}");
+        }
+    }
+
+    #[test]
+    fn test_human_solvers_perfect_maze() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let maze = Maze::new(15, 15).recursive_backtracker(&mut rng);
+        let (start, end) = (Pos::new(0, 0), Pos::new(14, 14));
+        let shortest = maze.breadth_first(start, end).unwrap().path;
+        let walks = [
+            maze.wall_follower(start, end, Hand::Left),
+            maze.wall_follower(start, end, Hand::Right),
+            maze.tremaux(start, end),
+        ];
+        for walk in &walks {
+            assert_eq!(walk.path.as_ref(), Some(&shortest));
+            assert_eq!(walk.visited.first(), Some(&start));
+            assert_eq!(walk.visited.last(), Some(&end));
+            assert!(walk.visited.len() >= shortest.len());
+            for step in walk.visited.windows(2) {
+                assert!(maze.neighbours(step[0]).any(|pos| pos == step[1]));
+            }
+        }
+        let filling = maze.dead_end_filling(start, end);
+        assert_eq!(filling.path, Some(shortest.clone()));
+        assert_eq!(filling.visited.len(), 15 * 15 - shortest.len());
+    }
+
+    #[test]
+    fn test_human_solvers_with_loops() {
+        let maze = braided_maze();
+        let (start, end) = (Pos::new(0, 0), Pos::new(19, 19));
+        let tremaux = maze.tremaux(start, end);
+        let filling = maze.dead_end_filling(start, end);
+        for walk in [&tremaux, &filling] {
+            let path = walk.path.as_ref().unwrap();
+            let search = Search {
+                path: path.clone(),
+                cost: 0,
+                stats: SearchStats::default(),
+            };
+            assert_walkable(&maze, &search, start, end);
+        }
+        // Filling leaves the shortest route in place
+        assert_eq!(
+            filling.path.unwrap().len(),
+            maze.breadth_first(start, end).unwrap().path.len()
+        );
+    }
+
+    #[test]
+    fn test_wall_follower_island() {
+        // A ring round the walled off (2,2), with a spur out to the end at (4,2)
+        let mut maze = Maze::new(5, 5);
+        for i in 1..3 {
+            maze.link(Pos::new(i, 1), Direction::East);
+            maze.link(Pos::new(i, 3), Direction::East);
+            maze.link(Pos::new(1, i), Direction::North);
+            maze.link(Pos::new(3, i), Direction::North);
+        }
+        maze.link(Pos::new(3, 2), Direction::East);
+        let (start, end) = (Pos::new(1, 2), Pos::new(4, 2));
+        let walks = [
+            maze.wall_follower(start, end, Hand::Left),
+            maze.wall_follower(start, end, Hand::Right),
+        ];
+        // Whichever hand is on the island just goes round and round it
+        assert_eq!(walks.iter().filter(|walk| walk.path.is_none()).count(), 1);
+        assert!(maze.tremaux(start, end).path.is_some());
+        assert!(maze.dead_end_filling(start, end).path.is_some());
+
+        let end = Pos::new(2, 2);
+        assert!(maze.wall_follower(start, end, Hand::Left).path.is_none());
+        assert!(maze.tremaux(start, end).path.is_none());
+        assert!(maze.dead_end_filling(start, end).path.is_none());
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let maze = Maze::new(3, 3);
+        assert!(maze.breadth_first(Pos::new(0, 0), Pos::new(2, 2)).is_none());
+        assert!(maze.a_star(Pos::new(0, 0), Pos::new(2, 2), &Heuristic::Manhattan).is_none());
+        let same = maze.a_star(Pos::new(1, 1), Pos::new(1, 1), &Heuristic::Manhattan).unwrap();
+        assert_eq!(same.path, vec![Pos::new(1, 1)]);
+        assert_eq!(same.cost, 0);
+    }
+}
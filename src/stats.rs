@@ -0,0 +1,190 @@
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+use std::fmt::Display;
+
+use crate::maze::{ALL, Maze};
+
+/* Difficulty numbers for a maze, solved from maze.start to maze.end */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MazeStats {
+    /* Unmasked cells */
+    pub cells: usize,
+    pub dead_ends: usize,
+    /* exits[n] is how many unmasked cells have n ways out */
+    pub exits: [usize; 5],
+    /* corridors[n] is how many runs of n cells there are with exactly two ways out */
+    pub corridors: Vec<usize>,
+    /* Share of cells in a corridor, high means long flowing passages */
+    pub river: f64,
+    /* -1 is all vertical links, 1 is all horizontal */
+    pub horizontal_bias: f64,
+    /* Cells on the shortest solution including start and end, 0 if there isn't one */
+    pub solution_length: usize,
+    pub solution_fraction: f64,
+    /* How far, on average, each side branch reaches away from the solution */
+    pub average_branch_length: f64,
+}
+
+impl Display for MazeStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "solution {} ({:.0}%), dead ends {}, junctions {}, river {:.2}, bias {:+.2}, branches {:.2}",
+            self.solution_length,
+            self.solution_fraction * 100.0,
+            self.dead_ends,
+            self.exits[3] + self.exits[4],
+            self.river,
+            self.horizontal_bias,
+            self.average_branch_length,
+        ))
+    }
+}
+
+impl Maze {
+    pub fn stats(&self) -> MazeStats {
+        let mut stats = MazeStats::default();
+        let open = self.all_pos().filter(|pos| !self.at_pos(*pos).masked);
+        let exits = self
+            .all_pos()
+            .map(|pos| ALL.iter().filter(|dir| self.can_go_pos(pos, **dir)).count())
+            .collect::<Vec<_>>();
+        for pos in open {
+            stats.cells += 1;
+            stats.exits[exits[self.index_of(pos)]] += 1;
+        }
+        stats.dead_ends = stats.exits[1];
+        stats.river = ratio(stats.exits[2], stats.cells);
+
+        let mut in_corridor = vec![false; self.cells.len()];
+        for pos in self.all_pos() {
+            if exits[self.index_of(pos)] != 2 || in_corridor[self.index_of(pos)] {
+                continue;
+            }
+            in_corridor[self.index_of(pos)] = true;
+            let mut length = 0;
+            let mut next = vec![pos];
+            while let Some(pos) = next.pop() {
+                length += 1;
+                for dir in ALL.iter().filter(|dir| self.can_go_pos(pos, **dir)) {
+                    let neighbour = self.index_of(pos.shift(*dir).unwrap());
+                    if exits[neighbour] == 2 && !in_corridor[neighbour] {
+                        in_corridor[neighbour] = true;
+                        next.push(pos.shift(*dir).unwrap());
+                    }
+                }
+            }
+            if stats.corridors.len() <= length {
+                stats.corridors.resize(length + 1, 0);
+            }
+            stats.corridors[length] += 1;
+        }
+
+        let horizontal = self.all_cells().filter(|cell| cell.right).count();
+        let vertical = self.all_cells().filter(|cell| cell.up).count();
+        stats.horizontal_bias = match horizontal + vertical {
+            0 => 0.0,
+            links => (horizontal as f64 - vertical as f64) / links as f64,
+        };
+
+        let Some(solution) = self.breadth_first(self.start, self.end) else {
+            return stats;
+        };
+        stats.solution_length = solution.path.len();
+        stats.solution_fraction = ratio(solution.path.len(), stats.cells);
+
+        // Spread out from the whole solution at once, remembering which branch each cell is on
+        let mut dist: Vec<Option<(usize, usize)>> = vec![None; self.cells.len()];
+        let mut frontier = VecDeque::new();
+        for pos in &solution.path {
+            dist[self.index_of(*pos)] = Some((0, usize::MAX));
+            frontier.push_back(*pos);
+        }
+        let mut branch_lengths: Vec<usize> = vec![];
+        while let Some(pos) = frontier.pop_front() {
+            let (length, branch) = dist[self.index_of(pos)].unwrap();
+            for dir in ALL.iter().filter(|dir| self.can_go_pos(pos, **dir)) {
+                let next = pos.shift(*dir).unwrap();
+                if dist[self.index_of(next)].is_some() {
+                    continue;
+                }
+                let branch = if length == 0 {
+                    branch_lengths.push(0);
+                    branch_lengths.len() - 1
+                } else {
+                    branch
+                };
+                branch_lengths[branch] = branch_lengths[branch].max(length + 1);
+                dist[self.index_of(next)] = Some((length + 1, branch));
+                frontier.push_back(next);
+            }
+        }
+        stats.average_branch_length = match branch_lengths.len() {
+            0 => 0.0,
+            branches => branch_lengths.iter().sum::<usize>() as f64 / branches as f64,
+        };
+        stats
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::maze::{Direction, Pos};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_stats_comb() {
+        // Solution along the bottom row with a branch up from every cell
+        //  | | | |
+        //  S-+-+-E
+        let mut maze = Maze::new(4, 3);
+        for x in 0..3 {
+            maze.link(Pos::new(x, 0), Direction::East);
+        }
+        for x in 0..4 {
+            maze.link(Pos::new(x, 0), Direction::North);
+        }
+        maze.link(Pos::new(1, 1), Direction::North);
+        maze.start = Pos::new(0, 0);
+        maze.end = Pos::new(3, 0);
+        let stats = maze.stats();
+        assert_eq!(stats.cells, 12);
+        assert_eq!(stats.exits, [3, 4, 3, 2, 0]);
+        assert_eq!(stats.dead_ends, 4);
+        assert_eq!(stats.corridors, vec![0, 3]);
+        assert_eq!(stats.solution_length, 4);
+        assert!((stats.solution_fraction - 4.0 / 12.0).abs() < 1e-9);
+        assert!((stats.horizontal_bias - (3.0 - 5.0) / 8.0).abs() < 1e-9);
+        assert!((stats.average_branch_length - 5.0 / 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stats_generated() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let mut maze = Maze::new(20, 15).recursive_backtracker(&mut rng);
+        maze.calc_longest();
+        let stats = maze.stats();
+        assert_eq!(stats.exits.iter().sum::<usize>(), 300);
+        let corridor_cells = stats
+            .corridors
+            .iter()
+            .enumerate()
+            .map(|(length, count)| length * count)
+            .sum::<usize>();
+        assert_eq!(corridor_cells, stats.exits[2]);
+        let (start, end) = (maze.start, maze.end);
+        let longest = maze.calc_dist(start).at_pos(end).dist.unwrap();
+        assert_eq!(stats.solution_length, longest as usize + 1);
+        assert!(stats.average_branch_length > 0.0);
+    }
+}
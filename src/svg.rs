@@ -0,0 +1,190 @@
+#![allow(dead_code)]
+
+use std::fmt::Write;
+
+use crate::maze::{Maze, Pos};
+
+/* Colours are anything SVG accepts, "#ff0000", "red", "rgb(255,0,0)" */
+#[derive(Clone, Debug)]
+pub struct SvgOptions {
+    pub cell_size: f64,
+    pub wall_thickness: f64,
+    pub background: String,
+    pub wall_colour: String,
+    pub masked_colour: String,
+    pub path_colour: String,
+    pub start_colour: String,
+    pub end_colour: String,
+    pub draw_path: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            cell_size: 20.0,
+            wall_thickness: 2.0,
+            background: "#ffffff".to_string(),
+            wall_colour: "#000000".to_string(),
+            masked_colour: "#808080".to_string(),
+            path_colour: "#0000ff".to_string(),
+            start_colour: "#00ff00".to_string(),
+            end_colour: "#ff0000".to_string(),
+            draw_path: true,
+        }
+    }
+}
+
+/* Draws the maze with north at the top, the same way up as render::make_image */
+pub fn make_svg(maze: &Maze, options: &SvgOptions) -> String {
+    let size = options.cell_size;
+    // Half a wall of room all round so the outside walls aren't clipped
+    let margin = options.wall_thickness / 2.0;
+    let width = maze.width() as f64 * size + margin * 2.0;
+    let height = maze.height() as f64 * size + margin * 2.0;
+    // Top left corner of a cell
+    let corner = |pos: Pos| {
+        (
+            margin + pos.x as f64 * size,
+            margin + (maze.height() - 1 - pos.y) as f64 * size,
+        )
+    };
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<rect width="{width}" height="{height}" fill="{}"/>"#,
+        options.background
+    )
+    .unwrap();
+
+    for pos in maze.all_pos() {
+        let cell = maze.at_pos(pos);
+        let fill = if cell.masked {
+            &options.masked_colour
+        } else if options.draw_path && cell.path == Some(true) {
+            &options.path_colour
+        } else {
+            continue;
+        };
+        let (x, y) = corner(pos);
+        writeln!(
+            svg,
+            r#"<rect x="{x}" y="{y}" width="{size}" height="{size}" fill="{fill}"/>"#
+        )
+        .unwrap();
+    }
+
+    for (pos, colour) in [
+        (maze.start, &options.start_colour),
+        (maze.end, &options.end_colour),
+    ] {
+        // Moving start or end off the maze hides it, the same as render does
+        if maze.at_pos_opt(pos).is_none() {
+            continue;
+        }
+        let (x, y) = corner(pos);
+        writeln!(
+            svg,
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{colour}"/>"#,
+            x + size / 2.0,
+            y + size / 2.0,
+            size / 3.0
+        )
+        .unwrap();
+    }
+
+    // Every wall as one path, each cell drawing its top and right side
+    let mut walls = String::new();
+    for pos in maze.all_pos() {
+        let cell = maze.at_pos(pos);
+        let (x, y) = corner(pos);
+        let both_masked = |other: Option<&crate::maze::Cell>| {
+            cell.masked && other.is_some_and(|other| other.masked)
+        };
+        let north = maze.at_opt(pos.x, pos.y + 1);
+        if !cell.up && !both_masked(north) {
+            write!(walls, "M{x} {y}H{}", x + size).unwrap();
+        }
+        let east = maze.at_opt(pos.x + 1, pos.y);
+        if !cell.right && !both_masked(east) {
+            write!(walls, "M{} {y}V{}", x + size, y + size).unwrap();
+        }
+    }
+    let (left, bottom) = (margin, margin + maze.height() as f64 * size);
+    write!(
+        walls,
+        "M{left} {margin}V{bottom}H{}",
+        margin + maze.width() as f64 * size
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<path d="{walls}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="square"/>"#,
+        options.wall_colour, options.wall_thickness
+    )
+    .unwrap();
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::maze::Direction;
+
+    #[test]
+    fn test_make_svg() {
+        // Two cells side by side with the wall between them open
+        let mut maze = Maze::new(2, 1);
+        maze.link(Pos::new(0, 0), Direction::East);
+        maze.start = Pos::new(0, 0);
+        maze.end = Pos::new(1, 0);
+        let options = SvgOptions {
+            cell_size: 10.0,
+            wall_thickness: 2.0,
+            ..SvgOptions::default()
+        };
+        let svg = make_svg(&maze, &options);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(r#"width="22" height="12""#));
+        // Two tops, the far right side, then the left and bottom
+        assert!(svg.contains(r#"d="M1 1H11M11 1H21M21 1V11M1 1V11H21""#));
+        assert!(svg.contains(r##"<circle cx="6" cy="6" r="3.3333333333333335" fill="#00ff00"/>"##));
+        assert!(svg.contains(r##"fill="#ff0000""##));
+    }
+
+    #[test]
+    fn test_make_svg_masked_and_path() {
+        let mut maze = Maze::new(3, 3);
+        maze.at_mut(0, 0).masked = true;
+        maze.at_mut(1, 0).masked = true;
+        maze.at_mut(2, 2).path = Some(true);
+        let svg = make_svg(&maze, &SvgOptions::default());
+        assert_eq!(svg.matches(r##"fill="#808080""##).count(), 2);
+        assert_eq!(svg.matches(r##"fill="#0000ff""##).count(), 1);
+        // No wall between the two masked cells
+        assert!(!svg.contains("M21 41V61"));
+
+        let options = SvgOptions {
+            draw_path: false,
+            ..SvgOptions::default()
+        };
+        let svg = make_svg(&maze, &options);
+        assert_eq!(svg.matches(r##"fill="#0000ff""##).count(), 0);
+    }
+
+    #[test]
+    fn test_make_svg_off_grid_markers() {
+        let mut maze = Maze::new(3, 1);
+        maze.start = Pos::new(5, 5);
+        maze.end = Pos::new(1, 3);
+        let svg = make_svg(&maze, &SvgOptions::default());
+        assert!(!svg.contains("<circle"));
+    }
+}
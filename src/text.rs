@@ -0,0 +1,644 @@
+#![allow(dead_code)]
+
+use std::fmt::Display;
+
+use image::Rgba;
+
+use crate::maze::{Direction, Maze, Pos};
+use crate::render::{BLUE, GREEN, Gradient, RED};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextStyle {
+    /* +--+ and | walls, safe anywhere */
+    Ascii,
+    /* Box drawing walls that join up properly */
+    Unicode,
+}
+
+#[derive(Clone, Debug)]
+pub struct TextOptions {
+    pub style: TextStyle,
+    /* Characters inside each cell, wide enough for the largest dist shown */
+    pub cell_width: usize,
+    pub show_dist: bool,
+    /* ANSI colours for the start, end and solution */
+    pub colour: bool,
+    /* ANSI background coloured by dist, needs a terminal with 24 bit colour */
+    pub heatmap: Option<Gradient>,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        TextOptions {
+            style: TextStyle::Unicode,
+            cell_width: 3,
+            show_dist: true,
+            colour: false,
+            heatmap: None,
+        }
+    }
+}
+
+/* Draws the maze with north at the top, one line of text per row of cells plus one per row of walls */
+pub fn make_text(maze: &Maze, options: &TextOptions) -> String {
+    let has_path = maze.all_cells().any(|cell| cell.path == Some(true));
+    let max_dist = maze
+        .all_cells()
+        .filter_map(|cell| cell.dist)
+        .max()
+        .unwrap_or(0);
+    let width = options.cell_width.max(1);
+    let (across, down, masked) = match options.style {
+        TextStyle::Ascii => ('-', '|', '#'),
+        TextStyle::Unicode => ('─', '│', '░'),
+    };
+
+    let mut text = String::new();
+    for vy in (0..=maze.height()).rev() {
+        for vx in 0..=maze.width() {
+            text.push(corner(maze, vx, vy, options.style));
+            if vx < maze.width() {
+                let wall = arms(maze, vx, vy).east;
+                text.extend(std::iter::repeat_n(if wall { across } else { ' ' }, width));
+            }
+        }
+        text.push('\n');
+        if vy == 0 {
+            break;
+        }
+
+        let y = vy - 1;
+        for x in 0..=maze.width() {
+            text.push(if arms(maze, x, vy).south { down } else { ' ' });
+            if x == maze.width() {
+                break;
+            }
+            let pos = Pos::new(x, y);
+            let cell = maze.at_pos(pos);
+            let label = if cell.masked {
+                masked.to_string().repeat(width)
+            } else if pos == maze.start {
+                marker(if width >= 3 { "STA" } else { "S" }, width)
+            } else if pos == maze.end {
+                marker(if width >= 3 { "END" } else { "E" }, width)
+            } else if has_path && cell.path != Some(true) {
+                " ".repeat(width)
+            } else {
+                match cell.dist.filter(|_| options.show_dist) {
+                    Some(dist) if dist.to_string().len() <= width => format!("{dist:>width$}"),
+                    // Too big to fit, rather than knocking the walls out of line
+                    Some(_) => "*".repeat(width),
+                    None if cell.path == Some(true) => marker("o", width),
+                    None => " ".repeat(width),
+                }
+            };
+
+            let background = if !options.colour || cell.masked {
+                None
+            } else if pos == maze.start {
+                Some(GREEN)
+            } else if pos == maze.end {
+                Some(RED)
+            } else if cell.path == Some(true) {
+                Some(BLUE)
+            } else {
+                None
+            };
+            let heat = options
+                .heatmap
+                .as_ref()
+                .zip(cell.dist)
+                .map(|(gradient, dist)| gradient.at(dist as f32 / max_dist.max(1) as f32));
+            match background.or(heat) {
+                Some(colour) => text.push_str(&paint(&label, colour)),
+                None => text.push_str(&label),
+            }
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn marker(label: &str, width: usize) -> String {
+    let label: String = label.chars().take(width).collect();
+    format!("{label:^width$}")
+}
+
+/* Label on a 24 bit background, with black or white text whichever reads better */
+fn paint(label: &str, colour: Rgba<u8>) -> String {
+    let [r, g, b, _] = colour.0;
+    let light = r as u32 * 299 + g as u32 * 587 + b as u32 * 114 > 128_000;
+    let text = if light { 30 } else { 97 };
+    format!("\x1b[{text};48;2;{r};{g};{b}m{label}\x1b[0m")
+}
+
+/* Which ways walls leave the corner at the bottom left of cell (vx, vy) */
+struct Arms {
+    north: bool,
+    east: bool,
+    south: bool,
+    west: bool,
+}
+
+fn arms(maze: &Maze, vx: usize, vy: usize) -> Arms {
+    let cell = |dx: usize, dy: usize| {
+        let pos = Pos::new(vx.checked_sub(dx)?, vy.checked_sub(dy)?);
+        maze.at_pos_opt(pos).map(|_| pos)
+    };
+    let (south_west, south_east, north_west, north_east) =
+        (cell(1, 1), cell(0, 1), cell(1, 0), cell(0, 0));
+    // Look at the wall from whichever side is inside the maze
+    let between = |first: Option<Pos>, first_dir: Direction, second: Option<Pos>| match first {
+        Some(pos) => wall(maze, pos, first_dir),
+        None => second.is_some_and(|pos| wall(maze, pos, first_dir.flip())),
+    };
+    Arms {
+        north: between(north_east, Direction::West, north_west),
+        east: between(north_east, Direction::South, south_east),
+        south: between(south_east, Direction::West, south_west),
+        west: between(north_west, Direction::South, south_west),
+    }
+}
+
+/* Walls between two masked cells, or a masked cell and the edge, are left out */
+fn wall(maze: &Maze, pos: Pos, dir: Direction) -> bool {
+    let neighbour = pos.shift(dir).and_then(|next| maze.at_pos_opt(next));
+    let masked_here = maze.at_pos(pos).masked;
+    let masked_there = neighbour.is_none_or(|cell| cell.masked);
+    if masked_here && masked_there {
+        return false;
+    }
+    neighbour.is_none() || !maze.can_go_pos(pos, dir)
+}
+
+fn corner(maze: &Maze, vx: usize, vy: usize, style: TextStyle) -> char {
+    let Arms {
+        mut north,
+        mut east,
+        mut south,
+        mut west,
+    } = arms(maze, vx, vy);
+    // Tick every cell boundary on the outside wall, like + does, so parse_text can tell
+    // where cells start even along a row with no walls inside it
+    if style == TextStyle::Unicode {
+        let along = (east || west, north || south);
+        north |= vy == 0 && along.0;
+        south |= vy == maze.height() && along.0;
+        east |= vx == 0 && along.1;
+        west |= vx == maze.width() && along.1;
+    }
+    match style {
+        TextStyle::Ascii if north || east || south || west => '+',
+        TextStyle::Ascii => ' ',
+        TextStyle::Unicode => match (north, east, south, west) {
+            (false, false, false, false) => ' ',
+            (true, false, false, false) => '╵',
+            (false, true, false, false) => '╶',
+            (false, false, true, false) => '╷',
+            (false, false, false, true) => '╴',
+            (true, false, true, false) => '│',
+            (false, true, false, true) => '─',
+            (true, true, false, false) => '└',
+            (true, false, false, true) => '┘',
+            (false, true, true, false) => '┌',
+            (false, false, true, true) => '┐',
+            (true, true, true, false) => '├',
+            (true, false, true, true) => '┤',
+            (false, true, true, true) => '┬',
+            (true, true, false, true) => '┴',
+            (true, true, true, true) => '┼',
+        },
+    }
+}
+
+/* Where in the text parse_text gave up, line and column count from 1 */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseErrorKind {
+    /* Nothing but blank lines */
+    Empty,
+    /* Rows of walls and rows of cells should take turns, starting and ending with walls */
+    RowCount(usize),
+    /* No + or box drawing corners to work out the cell width from */
+    NoCorners,
+    /* Something other than a wall or a gap where a wall should go */
+    Wall(char),
+    /* Cell text that isn't a dist, mask or START/END marker */
+    Cell(String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("line {}, column {}: ", self.line, self.column))?;
+        match &self.kind {
+            ParseErrorKind::Empty => f.write_str("no maze found"),
+            ParseErrorKind::RowCount(rows) => f.write_fmt(format_args!(
+                "{rows} lines can't be walls and cells taking turns"
+            )),
+            ParseErrorKind::NoCorners => f.write_str("no corners to size the cells by"),
+            ParseErrorKind::Wall(found) => {
+                f.write_fmt(format_args!("expected a wall, found '{found}'"))
+            }
+            ParseErrorKind::Cell(found) => f.write_fmt(format_args!("can't read cell '{found}'")),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+const CORNERS: &str = "+┌┐└┘├┤┬┴┼╵╶╷╴╔╗╚╝╠╣╦╩╬╟╢╤╧╭╮╯╰";
+const ACROSS: &str = "-─═";
+const DOWN: &str = "|│║";
+const MASKED: &str = "#░▒▓█";
+
+/* Reads back what make_text draws, ASCII or Unicode, without colour */
+pub fn parse_text(input: &str) -> Result<Maze, ParseError> {
+    let error = |line: usize, column: usize, kind| ParseError {
+        line: line + 1,
+        column: column + 1,
+        kind,
+    };
+    let mut lines: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
+    // Leading blank lines would throw the line numbers off, so only trim the end
+    while lines
+        .last()
+        .is_some_and(|line| line.iter().all(|c| *c == ' '))
+    {
+        lines.pop();
+    }
+    if lines.is_empty() {
+        return Err(error(0, 0, ParseErrorKind::Empty));
+    }
+    if lines.len() < 2 {
+        return Err(error(0, 0, ParseErrorKind::RowCount(lines.len())));
+    }
+    // A masked bottom row has no wall under it, so the last wall line was blank and trimmed
+    if lines.len().is_multiple_of(2) {
+        lines.push(vec![]);
+    }
+
+    // Corners sit every cell_width + 1 columns, even if some are missing around masked cells
+    let corners = lines
+        .iter()
+        .step_by(2)
+        .flat_map(|line| line.iter().enumerate())
+        .filter(|(_, c)| CORNERS.contains(**c))
+        .map(|(column, _)| column)
+        .collect::<Vec<_>>();
+    let step = corners.iter().fold(0, |step, column| gcd(step, *column));
+    if step < 2 {
+        return Err(error(0, 0, ParseErrorKind::NoCorners));
+    }
+    let cell_width = step - 1;
+    // Rounded up, as a masked right hand column has no outside wall after it
+    let longest = lines.iter().map(|line| line.len()).max().unwrap();
+    let width = (longest - 1).div_ceil(step);
+    let height = lines.len() / 2;
+    // Editors like to strip trailing spaces
+    let at = |line: usize, column: usize| lines[line].get(column).copied().unwrap_or(' ');
+
+    let mut maze = Maze::new(width, height);
+    let mut marked = (None, None);
+    for y in 0..height {
+        let line = 2 * (height - y) - 1;
+        for x in 0..width {
+            let column = x * step + 1;
+            let label: String = (column..column + cell_width).map(|c| at(line, c)).collect();
+            let cell = maze.at_mut(x, y);
+            if label.chars().all(|c| MASKED.contains(c)) {
+                cell.masked = true;
+                continue;
+            }
+            match label.trim() {
+                "" | "*" => {}
+                "STA" | "S" => marked.0 = Some(Pos::new(x, y)),
+                "END" | "E" => marked.1 = Some(Pos::new(x, y)),
+                "o" => cell.path = Some(true),
+                dist => match dist.parse() {
+                    Ok(dist) => cell.dist = Some(dist),
+                    Err(_) => return Err(error(line, column, ParseErrorKind::Cell(label))),
+                },
+            }
+        }
+    }
+
+    for y in 0..height {
+        let line = 2 * (height - y) - 1;
+        for x in 0..width {
+            let pos = Pos::new(x, y);
+            let column = (x + 1) * step;
+            if x + 1 < width
+                && open(at(line, column), DOWN).map_err(|kind| error(line, column, kind))?
+            {
+                link_unmasked(&mut maze, pos, Direction::East);
+            }
+            if y + 1 < height {
+                for column in x * step + 1..(x + 1) * step {
+                    let gap = open(at(line - 1, column), ACROSS)
+                        .map_err(|kind| error(line - 1, column, kind))?;
+                    if !gap {
+                        break;
+                    }
+                    if column == (x + 1) * step - 1 {
+                        link_unmasked(&mut maze, pos, Direction::North);
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(start) = marked.0 {
+        maze.start = start;
+    }
+    if let Some(end) = marked.1 {
+        maze.end = end;
+    }
+    Ok(maze)
+}
+
+/* True for a gap, false for a wall drawn with one of walls */
+fn open(found: char, walls: &str) -> Result<bool, ParseErrorKind> {
+    match found {
+        ' ' => Ok(true),
+        found if walls.contains(found) => Ok(false),
+        found => Err(ParseErrorKind::Wall(found)),
+    }
+}
+
+/* Walls between masked cells aren't drawn, so a gap there isn't a link */
+fn link_unmasked(maze: &mut Maze, pos: Pos, dir: Direction) {
+    let next = pos.shift(dir).unwrap();
+    if !(maze.at_pos(pos).masked && maze.at_pos(next).masked) {
+        maze.link(pos, dir);
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    /* The 3x2 maze from render's path test, along the bottom then up the right */
+    fn hook() -> Maze {
+        let mut maze = Maze::new(3, 2);
+        maze.link(Pos::new(0, 0), Direction::East);
+        maze.link(Pos::new(1, 0), Direction::East);
+        maze.link(Pos::new(2, 0), Direction::North);
+        maze.start = Pos::new(0, 0);
+        maze.end = Pos::new(2, 1);
+        maze
+    }
+
+    #[test]
+    fn test_ascii() {
+        let options = TextOptions {
+            style: TextStyle::Ascii,
+            show_dist: false,
+            ..TextOptions::default()
+        };
+        let expected = "\
++---+---+---+
+|   |   |END|
++---+---+   +
+|STA        |
++---+---+---+
+";
+        assert_eq!(make_text(&hook(), &options), expected);
+    }
+
+    #[test]
+    fn test_unicode() {
+        let mut maze = hook();
+        maze.at_mut(0, 1).masked = true;
+        maze.link(Pos::new(2, 1), Direction::West);
+        let maze = maze.calc_dist(Pos::new(0, 0));
+        let options = TextOptions {
+            cell_width: 2,
+            ..TextOptions::default()
+        };
+        // Lines are given one by one so the leading spaces survive
+        let expected = [
+            "   ┌──┬──┐",
+            " ░░│ 4 E │",
+            "┌──┴──╴  ┤",
+            "│S   1  2│",
+            "└──┴──┴──┘",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(make_text(&maze, &options), expected);
+    }
+
+    #[test]
+    fn test_wide_dist() {
+        let mut maze = Maze::new(4, 1);
+        for x in 0..3 {
+            maze.link(Pos::new(x, 0), Direction::East);
+        }
+        maze.start = Pos::new(9, 9);
+        maze.end = Pos::new(9, 9);
+        for (x, dist) in [5, 50, 500, 5000].into_iter().enumerate() {
+            maze.at_mut(x, 0).dist = Some(dist);
+        }
+        let options = TextOptions {
+            style: TextStyle::Ascii,
+            ..TextOptions::default()
+        };
+        let text = make_text(&maze, &options);
+        assert_eq!(text.lines().nth(1), Some("|  5  50 500 ***|"));
+        assert!(text.lines().all(|line| line.len() == 17));
+    }
+
+    #[test]
+    fn test_colour() {
+        let maze = hook().calc_dist(Pos::new(0, 0)).shortist_path();
+        let options = TextOptions {
+            colour: true,
+            ..TextOptions::default()
+        };
+        let text = make_text(&maze, &options);
+        assert!(text.contains("\x1b[97;48;2;0;0;255m  1\x1b[0m"));
+        assert!(text.contains("\x1b[30;48;2;0;255;0mSTA\x1b[0m"));
+        // The cell off the path gets no colour without a heatmap
+        assert!(text.contains("│   │"));
+
+        let options = TextOptions {
+            heatmap: Some(Gradient::new(BLUE, RED)),
+            ..TextOptions::default()
+        };
+        let text = make_text(&maze.clear_path().calc_dist(Pos::new(0, 0)), &options);
+        assert!(text.contains("\x1b[97;48;2;0;0;255mSTA\x1b[0m"));
+        assert!(text.contains("\x1b[97;48;2;255;0;0mEND\x1b[0m"));
+    }
+
+    fn walls(maze: &Maze) -> Vec<(bool, bool, bool)> {
+        maze.all_cells()
+            .map(|cell| (cell.up, cell.right, cell.masked))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let mut maze = Maze::new(7, 5);
+        maze.at_mut(0, 4).masked = true;
+        maze.at_mut(1, 4).masked = true;
+        maze.at_mut(0, 3).masked = true;
+        let mut maze = maze.recursive_backtracker(&mut rng);
+        maze.start = Pos::new(3, 2);
+        maze.end = Pos::new(6, 0);
+        for (style, cell_width) in [
+            (TextStyle::Ascii, 3),
+            (TextStyle::Unicode, 3),
+            (TextStyle::Ascii, 1),
+        ] {
+            let options = TextOptions {
+                style,
+                cell_width,
+                show_dist: false,
+                ..TextOptions::default()
+            };
+            let parsed = parse_text(&make_text(&maze, &options)).unwrap();
+            assert_eq!((parsed.width(), parsed.height()), (7, 5));
+            assert_eq!(walls(&parsed), walls(&maze));
+            assert_eq!((parsed.start, parsed.end), (maze.start, maze.end));
+        }
+    }
+
+    #[test]
+    fn test_parse_braided() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        for _ in 0..200 {
+            let (width, height) = (rng.random_range(2..=4), rng.random_range(2..=3));
+            let maze = Maze::new(width, height)
+                .recursive_backtracker(&mut rng)
+                .braid(1.0, &mut rng);
+            for style in [TextStyle::Ascii, TextStyle::Unicode] {
+                for cell_width in [1, 3] {
+                    let options = TextOptions {
+                        style,
+                        cell_width,
+                        show_dist: false,
+                        ..TextOptions::default()
+                    };
+                    let text = make_text(&maze, &options);
+                    let parsed = parse_text(&text).unwrap();
+                    assert_eq!((parsed.width(), parsed.height()), (width, height), "{text}");
+                    assert_eq!(walls(&parsed), walls(&maze), "{text}");
+                }
+            }
+        }
+
+        // Every wall inside knocked through leaves only the outside ones
+        let mut open = Maze::new(3, 2);
+        for pos in open.all_pos() {
+            open.at_pos_mut(pos).up = pos.y == 0;
+            open.at_pos_mut(pos).right = pos.x < 2;
+        }
+        let parsed = parse_text(&make_text(&open, &TextOptions::default())).unwrap();
+        assert_eq!(walls(&parsed), walls(&open));
+    }
+
+    #[test]
+    fn test_parse_corridor() {
+        let mut maze = Maze::new(5, 1);
+        for x in 0..4 {
+            maze.link(Pos::new(x, 0), Direction::East);
+        }
+        maze.end = Pos::new(9, 9);
+        let maze = maze.calc_dist(Pos::new(0, 0));
+        for cell_width in [1, 2, 3] {
+            let options = TextOptions {
+                cell_width,
+                ..TextOptions::default()
+            };
+            let parsed = parse_text(&make_text(&maze, &options)).unwrap();
+            assert_eq!((parsed.width(), parsed.height()), (5, 1));
+            assert_eq!(walls(&parsed), walls(&maze));
+            assert_eq!(parsed.start, Pos::new(0, 0));
+            assert_eq!(parsed.at(4, 0).dist, Some(4));
+        }
+    }
+
+    #[test]
+    fn test_parse_masked_edges() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12345);
+        let mut maze = Maze::new(4, 3);
+        for pos in maze.all_pos() {
+            if pos.x == 3 || pos.y == 0 {
+                maze.at_pos_mut(pos).masked = true;
+            }
+        }
+        let mut maze = maze.recursive_backtracker(&mut rng);
+        maze.start = Pos::new(0, 2);
+        maze.end = Pos::new(2, 1);
+        for style in [TextStyle::Ascii, TextStyle::Unicode] {
+            let options = TextOptions {
+                style,
+                show_dist: false,
+                ..TextOptions::default()
+            };
+            let text = make_text(&maze, &options);
+            // As saved by an editor that strips trailing spaces and blank lines
+            let stripped = text
+                .lines()
+                .map(|line| line.trim_end())
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim_end()
+                .to_string();
+            for text in [text, stripped] {
+                let parsed = parse_text(&text).unwrap();
+                assert_eq!((parsed.width(), parsed.height()), (4, 3), "{text}");
+                assert_eq!(walls(&parsed), walls(&maze), "{text}");
+                assert_eq!((parsed.start, parsed.end), (maze.start, maze.end));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_classic() {
+        // Hand written, with the trailing spaces an editor would strip
+        let text = "\
++--+--+
+|     |
++  +--+
+|S |E
++--+--+
+";
+        let maze = parse_text(text).unwrap();
+        assert!(maze.at(0, 0).up && maze.at(0, 1).right);
+        assert!(!maze.at(0, 0).right && !maze.at(1, 0).up);
+        assert_eq!((maze.start, maze.end), (Pos::new(0, 0), Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_text("+--+\n").err().unwrap();
+        assert_eq!(err.kind, ParseErrorKind::RowCount(1));
+        let err = parse_text("+--+--+\n|  x  |\n+--+--+\n").err().unwrap();
+        assert_eq!((err.line, err.column), (2, 4));
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 4: expected a wall, found 'x'"
+        );
+        let err = parse_text("+--+--+\n|  |ab|\n+--+--+\n").err().unwrap();
+        assert_eq!(err.to_string(), "line 2, column 5: can't read cell 'ab'");
+        assert_eq!(
+            parse_text("\n\n").err().unwrap().kind,
+            ParseErrorKind::Empty
+        );
+    }
+}